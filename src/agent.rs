@@ -0,0 +1,85 @@
+use crate::game::{Event, GameError, GameMove, GameState};
+use crate::poker::{Hand, HandValue};
+use crate::rules::GameRules;
+
+/// Everything one player is allowed to know when choosing a move.
+#[derive(Clone, Debug)]
+pub struct PlayerView {
+    pub seat: usize,
+    pub hand: Hand,
+    /// The number of cards held by each seat. Eliminated seats hold none.
+    pub card_counts: Vec<usize>,
+    pub penalties: Vec<u8>,
+    pub current_bet: Option<HandValue>,
+    /// The bets made so far this round, in order, with the seat that made them.
+    pub round_bets: Vec<(usize, HandValue)>,
+    pub rules: GameRules,
+}
+
+impl PlayerView {
+    /// Return the number of cards held by the other players.
+    pub fn num_unseen_cards(&self) -> usize {
+        self.card_counts.iter().sum::<usize>() - self.hand.cards.len()
+    }
+}
+
+/// Something that can choose moves for a seat: a person at the keyboard, a
+/// bot, or a remote player. An agent that needs to hand control back without
+/// moving returns GameError::TurnInterrupted.
+pub trait Agent {
+    fn choose_move(&mut self, view: &PlayerView) -> Result<GameMove, GameError>;
+}
+
+/// Ask the agent sitting in the current seat for a move and play it,
+/// returning the events the move caused.
+pub fn play_turn(
+    state: &mut GameState,
+    agents: &mut [Box<dyn Agent>],
+) -> Result<Vec<Event>, GameError> {
+    let seat = state.current_turn;
+    let view = state.player_view(seat);
+    let game_move = agents[seat].choose_move(&view)?;
+    state.process_move(game_move)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::agent;
+    use crate::card;
+    use crate::game;
+    use crate::poker;
+    use crate::rules;
+    use crate::testing::play_game;
+
+    /// Bets something that never exists when nobody has bet, otherwise calls.
+    struct Caller;
+
+    impl agent::Agent for Caller {
+        fn choose_move(
+            &mut self,
+            view: &agent::PlayerView,
+        ) -> Result<game::GameMove, game::GameError> {
+            Ok(match view.current_bet {
+                None => game::GameMove::Bet(poker::HandValue::Straight(card::Rank::Five)),
+                Some(_) => game::GameMove::Call(),
+            })
+        }
+    }
+
+    #[test]
+    fn agents_play_a_game() {
+        let mut state =
+            game::GameState::init_game_with_seed(3, rules::GameRules::default(), 5).unwrap();
+        let mut agents: Vec<Box<dyn agent::Agent>> = (0..3)
+            .map(|_| Box::new(Caller) as Box<dyn agent::Agent>)
+            .collect();
+        play_game(&mut state, &mut agents);
+    }
+
+    #[test]
+    fn num_unseen_cards() {
+        let state =
+            game::GameState::init_game_with_seed(4, rules::GameRules::default(), 5).unwrap();
+        assert_eq!(3, state.player_view(2).num_unseen_cards());
+    }
+}
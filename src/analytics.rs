@@ -0,0 +1,392 @@
+use crate::card::{Card, Rank, Suit};
+use crate::poker::{Commune, Deck, Hand, HandValue, PokerError};
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_chacha::ChaChaRng;
+
+/// The z-score of a two sided 95% confidence interval.
+pub(crate) const Z_95: f64 = 1.96;
+
+/// A group of interchangeable cards, of which the commune needs at least
+/// `needed` for a HandValue to exist.
+struct Requirement {
+    matches: Box<dyn Fn(&Card) -> bool>,
+    needed: usize,
+}
+
+/// Return the probability that the commune contains `value`, given the cards
+/// in `hand` and that the other players hold `num_unseen` cards dealt from
+/// the rest of `num_decks` standard decks.
+///
+/// The probability is computed exactly by counting every way the unseen
+/// cards can be dealt. HandValues that can never exist have probability 0.
+pub fn exact_probability(
+    value: HandValue,
+    hand: &Hand,
+    num_unseen: usize,
+    num_decks: u8,
+) -> Result<f64, PokerError> {
+    let pool = remaining_cards(&hand.cards, num_decks);
+    if num_unseen > pool.len() {
+        return Err(PokerError::NotEnoughCards(
+            "More cards are unseen than are left in the deck.".to_owned(),
+        ));
+    }
+    if !value.is_valid() {
+        return Ok(0.0);
+    }
+    let requirements = match requirements(value) {
+        None => return Ok(0.0),
+        Some(requirements) => requirements,
+    };
+
+    // Each requirement matches a disjoint group of the unseen pool. Split the
+    // pool into those groups plus everything else, and count how many still
+    // have to come from the unseen cards.
+    let mut group_sizes = vec![];
+    let mut still_needed = vec![];
+    for requirement in &requirements {
+        let in_hand = hand
+            .cards
+            .iter()
+            .filter(|card| (requirement.matches)(card))
+            .count();
+        let in_pool = pool
+            .iter()
+            .filter(|card| (requirement.matches)(card))
+            .count();
+        group_sizes.push(in_pool);
+        still_needed.push(requirement.needed.saturating_sub(in_hand));
+    }
+    let num_other_cards = pool.len() - group_sizes.iter().sum::<usize>();
+
+    let successful_deals = count_deals(&group_sizes, &still_needed, num_other_cards, num_unseen);
+    Ok(successful_deals / choose(pool.len(), num_unseen))
+}
+
+/// A sampled probability with its 95% confidence interval.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Estimate {
+    pub probability: f64,
+    pub lower: f64,
+    pub upper: f64,
+    pub samples: usize,
+}
+
+/// Estimate the probability that the commune contains `value` by dealing the
+/// `num_unseen` cards from the rest of `num_decks` decks `samples` times.
+///
+/// Unlike exact_probability this only relies on Commune::contains_handvalue,
+/// so it works for any HandValue the evaluator understands. The same seed
+/// always gives the same estimate. At least one sample is always drawn.
+pub fn estimate_probability(
+    value: HandValue,
+    hand: &Hand,
+    num_unseen: usize,
+    num_decks: u8,
+    samples: usize,
+    seed: u64,
+) -> Result<Estimate, PokerError> {
+    let mut pool = remaining_cards(&hand.cards, num_decks);
+    if num_unseen > pool.len() {
+        return Err(PokerError::NotEnoughCards(
+            "More cards are unseen than are left in the deck.".to_owned(),
+        ));
+    }
+    // Keep the deal order independent of how the pool was built.
+    pool.sort_by_key(|card| (card.rank, card.suit));
+    let samples = samples.max(1);
+    let mut rng = ChaChaRng::seed_from_u64(seed);
+    let mut commune = Commune {
+        cards: Vec::with_capacity(hand.cards.len() + num_unseen),
+    };
+    let mut successes = 0;
+    for _ in 0..samples {
+        let (unseen, _) = pool.partial_shuffle(&mut rng, num_unseen);
+        commune.cards.clear();
+        commune.cards.extend_from_slice(&hand.cards);
+        commune.cards.extend_from_slice(unseen);
+        if commune.contains_handvalue(value) {
+            successes += 1;
+        }
+    }
+    Ok(wilson_interval(successes, samples))
+}
+
+/// Return the Wilson score interval for `successes` out of `samples`, which
+/// stays inside [0, 1] even for probabilities close to either end. With no
+/// samples the interval is all of [0, 1].
+pub(crate) fn wilson_interval(successes: usize, samples: usize) -> Estimate {
+    if samples == 0 {
+        return Estimate {
+            probability: 0.0,
+            lower: 0.0,
+            upper: 1.0,
+            samples,
+        };
+    }
+    let n = samples as f64;
+    let p = successes as f64 / n;
+    let z2 = Z_95 * Z_95;
+    let center = (p + z2 / (2.0 * n)) / (1.0 + z2 / n);
+    let margin = Z_95 / (1.0 + z2 / n) * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt();
+    Estimate {
+        probability: p,
+        lower: (center - margin).max(0.0),
+        upper: (center + margin).min(1.0),
+        samples,
+    }
+}
+
+/// Return the cards left after removing `known` from num_decks full decks.
+fn remaining_cards(known: &[Card], num_decks: u8) -> Vec<Card> {
+    let mut pool = Deck::get_ordered_decks(num_decks).cards().to_vec();
+    for card in known {
+        if let Some(index) = pool.iter().position(|other| other == card) {
+            pool.swap_remove(index);
+        }
+    }
+    pool
+}
+
+fn requirements(value: HandValue) -> Option<Vec<Requirement>> {
+    let requirements = match value {
+        HandValue::HighCard(rank) => vec![of_rank(rank, 1)],
+        HandValue::OnePair(rank) => vec![of_rank(rank, 2)],
+        HandValue::TwoPair(first, second) => vec![of_rank(first, 2), of_rank(second, 2)],
+        HandValue::ThreeOfAKind(rank) => vec![of_rank(rank, 3)],
+        HandValue::Straight(top_rank) => Commune::ranks_in_straight(top_rank)?
+            .into_iter()
+            .map(|rank| of_rank(rank, 1))
+            .collect(),
+        HandValue::Flush(top_rank, suit) => vec![
+            of_card(Card {
+                suit,
+                rank: top_rank,
+            }),
+            Requirement {
+                matches: Box::new(move |card| card.suit == suit && card.rank < top_rank),
+                needed: 4,
+            },
+        ],
+        HandValue::FullHouse(three_of, two_of) => vec![of_rank(three_of, 3), of_rank(two_of, 2)],
+        HandValue::FourOfAKind(rank) => vec![of_rank(rank, 4)],
+        HandValue::StraightFlush(top_rank, suit) => straight_flush(top_rank, suit)?,
+        HandValue::RoyalFlush(suit) => straight_flush(Rank::Ace, suit)?,
+    };
+    Some(requirements)
+}
+
+fn of_rank(rank: Rank, needed: usize) -> Requirement {
+    Requirement {
+        matches: Box::new(move |card| card.rank == rank),
+        needed,
+    }
+}
+
+fn of_card(wanted: Card) -> Requirement {
+    Requirement {
+        matches: Box::new(move |card| *card == wanted),
+        needed: 1,
+    }
+}
+
+fn straight_flush(top_rank: Rank, suit: Suit) -> Option<Vec<Requirement>> {
+    Some(
+        Commune::ranks_in_straight(top_rank)?
+            .into_iter()
+            .map(|rank| of_card(Card { suit, rank }))
+            .collect(),
+    )
+}
+
+/// Count the ways to deal `num_cards` cards so that at least `still_needed[i]`
+/// come from the group of `group_sizes[i]` cards, for every group.
+fn count_deals(
+    group_sizes: &[usize],
+    still_needed: &[usize],
+    num_other_cards: usize,
+    num_cards: usize,
+) -> f64 {
+    let (group_size, needed) = match (group_sizes.first(), still_needed.first()) {
+        (Some(group_size), Some(needed)) => (*group_size, *needed),
+        _ => return choose(num_other_cards, num_cards),
+    };
+    let mut deals = 0.0;
+    for taken in needed..=group_size.min(num_cards) {
+        deals += choose(group_size, taken)
+            * count_deals(
+                &group_sizes[1..],
+                &still_needed[1..],
+                num_other_cards,
+                num_cards - taken,
+            );
+    }
+    deals
+}
+
+/// Return the binomial coefficient n choose k.
+fn choose(n: usize, k: usize) -> f64 {
+    if k > n {
+        return 0.0;
+    }
+    let k = k.min(n - k);
+    (0..k).fold(1.0, |acc, i| acc * (n - i) as f64 / (i + 1) as f64)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::analytics;
+    use crate::card;
+    use crate::poker;
+    use crate::testing::card;
+    use itertools::Itertools;
+
+    /// Count every possible deal of the unseen cards from a single deck.
+    fn brute_force(value: poker::HandValue, hand: &poker::Hand, num_unseen: usize) -> f64 {
+        let pool = analytics::remaining_cards(&hand.cards, 1);
+        let mut total = 0;
+        let mut found = 0;
+        for unseen in pool.into_iter().combinations(num_unseen) {
+            let mut cards = hand.cards.clone();
+            cards.extend(unseen);
+            total += 1;
+            if (poker::Commune { cards }).contains_handvalue(value) {
+                found += 1;
+            }
+        }
+        found as f64 / total as f64
+    }
+
+    #[test]
+    fn choose() {
+        assert_eq!(1.0, analytics::choose(5, 0));
+        assert_eq!(10.0, analytics::choose(5, 2));
+        assert_eq!(0.0, analytics::choose(2, 5));
+        assert_eq!(2_598_960.0, analytics::choose(52, 5).round());
+    }
+
+    #[test]
+    fn single_rank() {
+        let empty = poker::Hand::empty_hand();
+        let high_ace = poker::HandValue::HighCard(card::Rank::Ace);
+        let p = analytics::exact_probability(high_ace, &empty, 1, 1).unwrap();
+        assert!((p - 4.0 / 52.0).abs() < 1e-12);
+
+        let hand = poker::Hand {
+            cards: vec![card(card::Rank::Ace, card::Suit::Spades)],
+        };
+        let pair_aces = poker::HandValue::OnePair(card::Rank::Ace);
+        let p = analytics::exact_probability(pair_aces, &hand, 1, 1).unwrap();
+        assert!((p - 3.0 / 51.0).abs() < 1e-12);
+        let p = analytics::exact_probability(high_ace, &hand, 0, 1).unwrap();
+        assert_eq!(1.0, p);
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        use card::Rank::*;
+        use card::Suit::*;
+        let hand = poker::Hand {
+            cards: vec![card(Ten, Spades), card(Jack, Spades)],
+        };
+        let values = vec![
+            poker::HandValue::HighCard(Two),
+            poker::HandValue::OnePair(Ten),
+            poker::HandValue::TwoPair(Jack, Ten),
+            poker::HandValue::TwoPair(Ace, King),
+            poker::HandValue::ThreeOfAKind(Jack),
+            poker::HandValue::Straight(Ace),
+            poker::HandValue::Straight(King),
+            poker::HandValue::Straight(Five),
+            poker::HandValue::Flush(King, Spades),
+            poker::HandValue::Flush(Jack, Spades),
+            poker::HandValue::FullHouse(Ten, Jack),
+            poker::HandValue::FourOfAKind(Ten),
+            poker::HandValue::StraightFlush(King, Spades),
+            poker::HandValue::RoyalFlush(Spades),
+            poker::HandValue::RoyalFlush(Hearts),
+        ];
+        for value in values {
+            let exact = analytics::exact_probability(value, &hand, 3, 1).unwrap();
+            let expected = brute_force(value, &hand, 3);
+            assert!(
+                (exact - expected).abs() < 1e-9,
+                "{:?}: {} != {}",
+                value,
+                exact,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn impossible_values() {
+        let empty = poker::Hand::empty_hand();
+        let p = analytics::exact_probability(
+            poker::HandValue::Straight(card::Rank::Four),
+            &empty,
+            10,
+            1,
+        )
+        .unwrap();
+        assert_eq!(0.0, p);
+        let too_many = analytics::exact_probability(
+            poker::HandValue::HighCard(card::Rank::Two),
+            &empty,
+            53,
+            1,
+        );
+        assert!(too_many.is_err());
+    }
+
+    #[test]
+    fn wilson_interval() {
+        let estimate = analytics::wilson_interval(50, 100);
+        assert_eq!(0.5, estimate.probability);
+        assert!(estimate.lower < 0.5 && estimate.upper > 0.5);
+        assert!((estimate.upper - estimate.lower - 0.19).abs() < 0.01);
+        let never = analytics::wilson_interval(0, 100);
+        assert_eq!(0.0, never.lower);
+        assert!(never.upper > 0.0);
+    }
+
+    #[test]
+    fn estimate_agrees_with_exact() {
+        use card::Rank::*;
+        use card::Suit::*;
+        let hand = poker::Hand {
+            cards: vec![card(Queen, Hearts), card(Nine, Clubs), card(Nine, Spades)],
+        };
+        let values = vec![
+            poker::HandValue::OnePair(Queen),
+            poker::HandValue::FullHouse(Nine, Queen),
+            poker::HandValue::Straight(King),
+            poker::HandValue::Flush(Ace, Hearts),
+        ];
+        for value in values {
+            let exact = analytics::exact_probability(value, &hand, 12, 1).unwrap();
+            let estimate =
+                analytics::estimate_probability(value, &hand, 12, 1, 20_000, 11).unwrap();
+            assert!(
+                estimate.lower <= exact && exact <= estimate.upper,
+                "{:?}: {} not in {:?}",
+                value,
+                exact,
+                estimate
+            );
+        }
+    }
+
+    #[test]
+    fn estimate_is_seeded() {
+        let hand = poker::Hand::empty_hand();
+        let value = poker::HandValue::OnePair(card::Rank::Ace);
+        let first = analytics::estimate_probability(value, &hand, 8, 2, 500, 3).unwrap();
+        let second = analytics::estimate_probability(value, &hand, 8, 2, 500, 3).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(500, first.samples);
+        let too_many = analytics::estimate_probability(value, &hand, 105, 2, 500, 3);
+        assert!(too_many.is_err());
+    }
+}
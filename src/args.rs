@@ -0,0 +1,40 @@
+//! Reading options from the command line of the helper binaries.
+
+use std::str::FromStr;
+
+/// Return the value following `name` on the command line.
+pub fn option<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|arg| arg == name)
+        .and_then(|index| args.get(index + 1))
+        .map(|value| value.as_str())
+}
+
+/// Return the number following `name` on the command line, or `default` if
+/// the option is missing.
+pub fn number<T: FromStr>(args: &[String], name: &str, default: T) -> Result<T, String> {
+    match option(args, name) {
+        None => Ok(default),
+        Some(value) => value
+            .parse()
+            .map_err(|_| format!("{} expects a number, not \"{}\".", name, value)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::args;
+
+    #[test]
+    fn options() {
+        let args: Vec<String> = vec!["solve", "--cards", "2", "--out"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        assert_eq!(Some("2"), args::option(&args, "--cards"));
+        assert_eq!(None, args::option(&args, "--out"));
+        assert_eq!(Ok(2), args::number(&args, "--cards", 1));
+        assert_eq!(Ok(7), args::number(&args, "--games", 7));
+        assert!(args::number::<usize>(&args, "solve", 1).is_err());
+    }
+}
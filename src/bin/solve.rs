@@ -0,0 +1,56 @@
+use commune::args::{number, option};
+use commune::card::{ParseError, Rank, Suit};
+use commune::cfr::{Solver, SolverConfig};
+use std::env;
+use std::fs;
+use std::process;
+
+const USAGE: &str = "Usage: solve [--ranks J,Q,K] [--suits H,S] [--cards N] \
+                     [--iterations N] [--out FILE]";
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let (config, iterations) = match parse_config(&args) {
+        Ok(config) => config,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            process::exit(2);
+        }
+    };
+    let mut solver = Solver::new(&config);
+    solver.train(iterations);
+    println!("Iterations: {}", solver.iterations);
+    println!("Game value: {:.4}", solver.game_value());
+    println!("Exploitability: {:.4}", solver.exploitability());
+    if let Some(path) = option(&args, "--out") {
+        if let Err(e) = fs::write(path, solver.strategy().to_text()) {
+            eprintln!("Could not write {}: {}", path, e);
+            process::exit(1);
+        }
+    }
+}
+
+fn parse_config(args: &[String]) -> Result<(SolverConfig, usize), String> {
+    let ranks = option(args, "--ranks")
+        .unwrap_or("J,Q,K")
+        .split(',')
+        .map(|rank| rank.parse().map_err(|e: ParseError| e.to_string()))
+        .collect::<Result<Vec<Rank>, String>>()?;
+    let suits = option(args, "--suits")
+        .unwrap_or("H,S")
+        .split(',')
+        .map(|suit| suit.parse().map_err(|e: ParseError| e.to_string()))
+        .collect::<Result<Vec<Suit>, String>>()?;
+    let hand_size = number(args, "--cards", 1)?;
+    if hand_size == 0 || hand_size > 2 || 2 * hand_size > ranks.len() * suits.len() {
+        return Err(
+            "Each player needs 1 or 2 cards, and the deck must hold both hands.".to_owned(),
+        );
+    }
+    let config = SolverConfig {
+        ranks,
+        suits,
+        hand_size,
+    };
+    Ok((config, number(args, "--iterations", 1000)?))
+}
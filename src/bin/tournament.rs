@@ -0,0 +1,53 @@
+use commune::args::{number, option};
+use commune::rules::GameRules;
+use commune::tournament::{self, AgentSpec, TournamentConfig};
+use std::env;
+use std::fs;
+use std::process;
+
+const USAGE: &str = "Usage: tournament [--games N] [--seed N] [--max-turns N] \
+                     [--lineup prob:0.3:0.1,mcts:200,strategy:FILE] \
+                     [--json FILE]";
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let config = match parse_config(&args) {
+        Ok(config) => config,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            process::exit(2);
+        }
+    };
+    let report = match tournament::run_tournament(&config) {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    };
+    print!("{}", report.to_table());
+    if let Some(path) = option(&args, "--json") {
+        if let Err(e) = fs::write(path, report.to_json()) {
+            eprintln!("Could not write {}: {}", path, e);
+            process::exit(1);
+        }
+    }
+}
+
+fn parse_config(args: &[String]) -> Result<TournamentConfig, String> {
+    let lineup = option(args, "--lineup")
+        .unwrap_or("prob,prob:0.6:0.0,prob:0.1:0.3")
+        .split(',')
+        .map(|spec| spec.parse::<AgentSpec>())
+        .collect::<Result<Vec<AgentSpec>, String>>()?;
+    if lineup.len() < 2 {
+        return Err("A lineup needs at least two agents.".to_owned());
+    }
+    Ok(TournamentConfig {
+        lineup,
+        num_games: number(args, "--games", 1000)?,
+        seed: number(args, "--seed", 0)?,
+        rules: GameRules::default(),
+        max_turns: number(args, "--max-turns", 10_000)?,
+    })
+}
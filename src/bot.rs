@@ -0,0 +1,224 @@
+use crate::agent::{Agent, PlayerView};
+use crate::analytics;
+use crate::cfr::{self, Strategy};
+use crate::game::{GameError, GameMove};
+use crate::poker::HandValue;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaChaRng;
+
+/// How many of the lowest legal raises a bluff picks from.
+const BLUFF_CHOICES: usize = 5;
+
+/// A computer player that bets on the odds of a HandValue existing in the
+/// commune, given its own hand and how many cards everybody holds.
+#[derive(Clone, Debug)]
+pub struct ProbabilityBot {
+    /// Between 0 and 1. The chance the bot assumes that a raise goes
+    /// unchallenged, so higher values raise more and call less.
+    pub aggression: f64,
+    /// Between 0 and 1. How often the bot raises without looking at the odds.
+    pub bluff_frequency: f64,
+    rng: ChaChaRng,
+}
+
+impl ProbabilityBot {
+    pub fn new(aggression: f64, bluff_frequency: f64, seed: u64) -> Self {
+        Self {
+            aggression,
+            bluff_frequency,
+            rng: ChaChaRng::seed_from_u64(seed),
+        }
+    }
+
+    fn probability(&self, value: HandValue, view: &PlayerView) -> f64 {
+        analytics::exact_probability(
+            value,
+            &view.hand,
+            view.num_unseen_cards(),
+            view.rules.num_decks,
+        )
+        .unwrap_or(0.0)
+    }
+}
+
+impl Agent for ProbabilityBot {
+    fn choose_move(&mut self, view: &PlayerView) -> Result<GameMove, GameError> {
+        let raises: Vec<HandValue> = HandValue::all_values()
+            .into_iter()
+            .filter(|value| view.current_bet.map_or(true, |bet| *value > bet))
+            .collect();
+        if raises.is_empty() {
+            return Ok(GameMove::Call());
+        }
+        if self.rng.gen_bool(self.bluff_frequency.clamp(0.0, 1.0)) {
+            let choice = self.rng.gen_range(0, raises.len().min(BLUFF_CHOICES));
+            return Ok(GameMove::Bet(raises[choice]));
+        }
+
+        // The most plausible raise, preferring the higher bet on ties.
+        let mut best_raise = raises[0];
+        let mut best_probability = self.probability(best_raise, view);
+        for &raise in &raises[1..] {
+            let probability = self.probability(raise, view);
+            if probability >= best_probability {
+                best_raise = raise;
+                best_probability = probability;
+            }
+        }
+
+        match view.current_bet {
+            None => Ok(GameMove::Bet(best_raise)),
+            Some(bet) => {
+                // Calling loses if the bet exists. Raising loses if the raise
+                // does not exist and the next player challenges it.
+                let risk_of_calling = self.probability(bet, view);
+                let risk_of_raising = (1.0 - best_probability) * (1.0 - self.aggression);
+                if risk_of_calling < risk_of_raising {
+                    Ok(GameMove::Call())
+                } else {
+                    Ok(GameMove::Bet(best_raise))
+                }
+            }
+        }
+    }
+}
+
+/// A computer player that follows a strategy table exported by the solver.
+/// Information sets missing from the table are played safely: call a bet,
+/// or open with the lowest HandValue.
+#[derive(Clone, Debug)]
+pub struct StrategyBot {
+    pub strategy: Strategy,
+    rng: ChaChaRng,
+}
+
+impl StrategyBot {
+    pub fn new(strategy: Strategy, seed: u64) -> Self {
+        Self {
+            strategy,
+            rng: ChaChaRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Agent for StrategyBot {
+    fn choose_move(&mut self, view: &PlayerView) -> Result<GameMove, GameError> {
+        let mut legal_moves: Vec<GameMove> = HandValue::all_values()
+            .into_iter()
+            .filter(|value| view.current_bet.map_or(true, |bet| *value > bet))
+            .map(GameMove::Bet)
+            .collect();
+        if view.current_bet.is_some() {
+            legal_moves.insert(0, GameMove::Call());
+        }
+        let fallback = legal_moves[0];
+
+        let bets: Vec<HandValue> = view.round_bets.iter().map(|(_, bet)| *bet).collect();
+        let key = cfr::info_set_key(&view.hand.cards, &bets);
+        let choices: Vec<(GameMove, f64)> = match self.strategy.get(&key) {
+            None => return Ok(fallback),
+            Some(actions) => actions
+                .iter()
+                .filter_map(|(label, probability)| {
+                    legal_moves
+                        .iter()
+                        .find(|&&legal_move| cfr::action_label(legal_move) == *label)
+                        .map(|&legal_move| (legal_move, *probability))
+                })
+                .collect(),
+        };
+
+        let total: f64 = choices.iter().map(|(_, probability)| probability).sum();
+        if total <= 0.0 {
+            return Ok(fallback);
+        }
+        let mut remaining = self.rng.gen_range(0.0, total);
+        for (choice, probability) in &choices {
+            if remaining < *probability {
+                return Ok(*choice);
+            }
+            remaining -= probability;
+        }
+        Ok(choices[choices.len() - 1].0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::agent::{self, Agent};
+    use crate::bot;
+    use crate::card;
+    use crate::cfr;
+    use crate::game;
+    use crate::poker;
+    use crate::rules;
+    use crate::testing::{card, play_game, view};
+
+    #[test]
+    fn opens_with_a_sure_thing() {
+        let mut bot = bot::ProbabilityBot::new(0.0, 0.0, 1);
+        let hand = vec![
+            card(card::Rank::Nine, card::Suit::Clubs),
+            card(card::Rank::Nine, card::Suit::Hearts),
+        ];
+        let game_move = bot.choose_move(&view(hand, None)).unwrap();
+        assert_eq!(
+            game::GameMove::Bet(poker::HandValue::OnePair(card::Rank::Nine)),
+            game_move
+        );
+    }
+
+    #[test]
+    fn calls_an_unlikely_bet() {
+        let mut bot = bot::ProbabilityBot::new(0.0, 0.0, 1);
+        let hand = vec![
+            card(card::Rank::Two, card::Suit::Clubs),
+            card(card::Rank::Seven, card::Suit::Hearts),
+        ];
+        let bet = Some(poker::HandValue::FourOfAKind(card::Rank::Ace));
+        assert_eq!(
+            game::GameMove::Call(),
+            bot.choose_move(&view(hand, bet)).unwrap()
+        );
+    }
+
+    #[test]
+    fn raises_over_a_weak_bet() {
+        let mut bot = bot::ProbabilityBot::new(0.0, 0.0, 1);
+        let hand = vec![
+            card(card::Rank::King, card::Suit::Clubs),
+            card(card::Rank::King, card::Suit::Hearts),
+        ];
+        let bet = Some(poker::HandValue::HighCard(card::Rank::Ace));
+        assert_eq!(
+            game::GameMove::Bet(poker::HandValue::OnePair(card::Rank::King)),
+            bot.choose_move(&view(hand, bet)).unwrap()
+        );
+    }
+
+    #[test]
+    fn bots_finish_a_game() {
+        let mut state =
+            game::GameState::init_game_with_seed(3, rules::GameRules::default(), 9).unwrap();
+        let mut agents: Vec<Box<dyn agent::Agent>> = (0..3)
+            .map(|seat| Box::new(bot::ProbabilityBot::new(0.3, 0.1, seat)) as Box<dyn Agent>)
+            .collect();
+        play_game(&mut state, &mut agents);
+    }
+
+    #[test]
+    fn follows_a_strategy() {
+        let hand = vec![card(card::Rank::Jack, card::Suit::Hearts)];
+        let bet = poker::HandValue::HighCard(card::Rank::King);
+        let text = "JH|HC K\tcall=0\t1P J=1\n";
+        let strategy = cfr::Strategy::from_text(text).unwrap();
+        let mut bot = bot::StrategyBot::new(strategy, 1);
+        let mut view = view(hand, Some(bet));
+        assert_eq!(
+            game::GameMove::Bet(poker::HandValue::OnePair(card::Rank::Jack)),
+            bot.choose_move(&view).unwrap()
+        );
+        view.round_bets = vec![];
+        assert_eq!(game::GameMove::Call(), bot.choose_move(&view).unwrap());
+    }
+}
@@ -1,8 +1,13 @@
 use std::cmp::Ordering;
 use std::fmt;
+use std::str::FromStr;
 
+#[cfg(feature = "serde")]
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use strum::IntoEnumIterator;
 
+/// Suits are declared in the order the deck is built in, which seeded deals
+/// depend on. They compare in bridge order instead; see `Ord for Suit`.
 #[derive(Clone, Copy, Debug, EnumIter, Eq, PartialEq)]
 pub enum Suit {
     Clubs,
@@ -34,6 +39,28 @@ pub struct Card {
     pub rank: Rank,
 }
 
+/// Text that could not be read as a rank, suit, card or hand.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParseError {
+    pub input: String,
+    pub reason: String,
+}
+
+impl ParseError {
+    pub fn new(input: &str, reason: impl Into<String>) -> Self {
+        Self {
+            input: input.trim().to_owned(),
+            reason: reason.into(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Could not read \"{}\": {}", self.input, self.reason)
+    }
+}
+
 #[derive(Clone, Copy, Debug, EnumIter, Eq, PartialEq)]
 pub enum LineNumber {
     Zero,
@@ -46,52 +73,65 @@ pub enum LineNumber {
 impl fmt::Display for Suit {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let representation = match self {
-            &Suit::Clubs => "♣",
-            &Suit::Diamonds => "♦",
-            &Suit::Hearts => "♥",
-            &Suit::Spades => "♠",
+            Suit::Clubs => "♣",
+            Suit::Diamonds => "♦",
+            Suit::Hearts => "♥",
+            Suit::Spades => "♠",
         };
         write!(f, "{}", representation)
     }
 }
 
+impl fmt::Display for Card {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let num_lines = LineNumber::iter().len();
+        for (i, line) in LineNumber::iter().enumerate() {
+            write!(f, "{}", self.to_single_string(line))?;
+            if i < num_lines - 1 {
+                writeln!(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
 impl fmt::Display for Rank {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let representation = match self {
-            &Rank::Two => "2",
-            &Rank::Three => "3",
-            &Rank::Four => "4",
-            &Rank::Five => "5",
-            &Rank::Six => "6",
-            &Rank::Seven => "7",
-            &Rank::Eight => "8",
-            &Rank::Nine => "9",
-            &Rank::Ten => "10",
-            &Rank::Jack => "J",
-            &Rank::Queen => "Q",
-            &Rank::King => "K",
-            &Rank::Ace => "A",
+            Rank::Two => "2",
+            Rank::Three => "3",
+            Rank::Four => "4",
+            Rank::Five => "5",
+            Rank::Six => "6",
+            Rank::Seven => "7",
+            Rank::Eight => "8",
+            Rank::Nine => "9",
+            Rank::Ten => "10",
+            Rank::Jack => "J",
+            Rank::Queen => "Q",
+            Rank::King => "K",
+            Rank::Ace => "A",
         };
         write!(f, "{}", representation)
     }
 }
 
 impl Rank {
-    pub fn to_u8(&self) -> u8 {
+    pub fn to_u8(self) -> u8 {
         match self {
-            &Rank::Two => 2,
-            &Rank::Three => 3,
-            &Rank::Four => 4,
-            &Rank::Five => 5,
-            &Rank::Six => 6,
-            &Rank::Seven => 7,
-            &Rank::Eight => 8,
-            &Rank::Nine => 9,
-            &Rank::Ten => 10,
-            &Rank::Jack => 11,
-            &Rank::Queen => 12,
-            &Rank::King => 13,
-            &Rank::Ace => 14,
+            Rank::Two => 2,
+            Rank::Three => 3,
+            Rank::Four => 4,
+            Rank::Five => 5,
+            Rank::Six => 6,
+            Rank::Seven => 7,
+            Rank::Eight => 8,
+            Rank::Nine => 9,
+            Rank::Ten => 10,
+            Rank::Jack => 11,
+            Rank::Queen => 12,
+            Rank::King => 13,
+            Rank::Ace => 14,
         }
     }
 
@@ -114,7 +154,35 @@ impl Rank {
         }
     }
 
-    pub fn from_str(s: &str) -> Option<Rank> {
+    /// Return the rank's name, e.g. "Queen".
+    pub fn name(self) -> &'static str {
+        match self {
+            Rank::Two => "Two",
+            Rank::Three => "Three",
+            Rank::Four => "Four",
+            Rank::Five => "Five",
+            Rank::Six => "Six",
+            Rank::Seven => "Seven",
+            Rank::Eight => "Eight",
+            Rank::Nine => "Nine",
+            Rank::Ten => "Ten",
+            Rank::Jack => "Jack",
+            Rank::Queen => "Queen",
+            Rank::King => "King",
+            Rank::Ace => "Ace",
+        }
+    }
+
+    /// Return the name for more than one card of the rank, e.g. "Sixes".
+    pub fn plural(self) -> String {
+        match self {
+            Rank::Six => "Sixes".to_owned(),
+            _ => format!("{}s", self.name()),
+        }
+    }
+
+    /// Read a rank written by Display, e.g. "Q" or "10".
+    pub fn from_symbol(s: &str) -> Option<Rank> {
         match s {
             "2" => Some(Rank::Two),
             "3" => Some(Rank::Three),
@@ -134,9 +202,155 @@ impl Rank {
     }
 }
 
+impl Suit {
+    /// Return the letter used for the suit in plain text, e.g. 'H'.
+    pub fn letter(self) -> char {
+        match self {
+            Suit::Clubs => 'C',
+            Suit::Diamonds => 'D',
+            Suit::Hearts => 'H',
+            Suit::Spades => 'S',
+        }
+    }
+
+    /// Return the suit's name, e.g. "Hearts".
+    pub fn name(self) -> &'static str {
+        match self {
+            Suit::Clubs => "Clubs",
+            Suit::Diamonds => "Diamonds",
+            Suit::Hearts => "Hearts",
+            Suit::Spades => "Spades",
+        }
+    }
+
+    /// Read a suit written by letter.
+    pub fn from_letter(s: &str) -> Option<Suit> {
+        match s {
+            "C" => Some(Suit::Clubs),
+            "D" => Some(Suit::Diamonds),
+            "H" => Some(Suit::Hearts),
+            "S" => Some(Suit::Spades),
+            _ => None,
+        }
+    }
+}
+
+impl PartialOrd for Suit {
+    fn partial_cmp(&self, other: &Suit) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Clubs, then Diamonds, then Hearts, then Spades, which breaks ties between
+/// flushes of the same rank.
+impl Ord for Suit {
+    fn cmp(&self, other: &Suit) -> Ordering {
+        let order = |suit: &Suit| match suit {
+            Suit::Clubs => 0,
+            Suit::Diamonds => 1,
+            Suit::Hearts => 2,
+            Suit::Spades => 3,
+        };
+        order(self).cmp(&order(other))
+    }
+}
+
+/// Reads a rank typed by a player, ignoring case: a symbol ("Q"), a number
+/// ("12"), or a name, singular or plural ("queen", "queens").
+impl FromStr for Rank {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let word = s.trim().to_lowercase();
+        // Only numbers and names are pluralized: "KS" is a card, not kings.
+        let singular = match word.strip_suffix('s') {
+            _ if word == "sixes" => "six",
+            Some(number) if !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()) => {
+                number
+            }
+            Some(name) if rank_name(name).is_some() => name,
+            _ => &word,
+        };
+        if let Ok(number) = singular.parse() {
+            return Rank::from_u8(number)
+                .ok_or_else(|| ParseError::new(s, "ranks are numbered from 2 to 14"));
+        }
+        let rank = match singular {
+            "t" => Some(Rank::Ten),
+            "j" => Some(Rank::Jack),
+            "q" => Some(Rank::Queen),
+            "k" => Some(Rank::King),
+            "a" => Some(Rank::Ace),
+            name => rank_name(name),
+        };
+        rank.ok_or_else(|| ParseError::new(s, "expected a rank such as Q, queens or 12"))
+    }
+}
+
+/// Read a rank spelled out in lowercase, e.g. "queen" or "deuce".
+fn rank_name(name: &str) -> Option<Rank> {
+    match name {
+        "two" | "deuce" => Some(Rank::Two),
+        "three" | "trey" => Some(Rank::Three),
+        "four" => Some(Rank::Four),
+        "five" => Some(Rank::Five),
+        "six" => Some(Rank::Six),
+        "seven" => Some(Rank::Seven),
+        "eight" => Some(Rank::Eight),
+        "nine" => Some(Rank::Nine),
+        "ten" => Some(Rank::Ten),
+        "jack" => Some(Rank::Jack),
+        "queen" => Some(Rank::Queen),
+        "king" => Some(Rank::King),
+        "ace" => Some(Rank::Ace),
+        _ => None,
+    }
+}
+
+/// Reads a suit typed by a player, ignoring case: a letter ("H"), a symbol
+/// ("♥") or a name ("hearts").
+impl FromStr for Suit {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let word = s.trim().to_lowercase();
+        let suit = match word.as_str() {
+            "c" | "♣" | "club" | "clubs" => Suit::Clubs,
+            "d" | "♦" | "diamond" | "diamonds" => Suit::Diamonds,
+            "h" | "♥" | "heart" | "hearts" => Suit::Hearts,
+            "s" | "♠" | "spade" | "spades" => Suit::Spades,
+            _ => return Err(ParseError::new(s, "expected a suit such as H, ♥ or hearts")),
+        };
+        Ok(suit)
+    }
+}
+
+/// Reads a card typed by a player: a rank followed by a suit, either run
+/// together ("10H", "q♠") or as words ("queen of spades").
+impl FromStr for Card {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words: Vec<&str> = s.split_whitespace().collect();
+        let (rank, suit) = match words.as_slice() {
+            [rank, "of", suit] | [rank, suit] => (*rank, *suit),
+            [word] => {
+                let split = word.char_indices().last().map_or(0, |(i, _)| i);
+                word.split_at(split)
+            }
+            _ => return Err(ParseError::new(s, "expected a card such as QS")),
+        };
+        let wrap = |e: ParseError| ParseError::new(s, e.reason);
+        Ok(Card {
+            rank: rank.parse().map_err(wrap)?,
+            suit: suit.parse().map_err(wrap)?,
+        })
+    }
+}
+
 impl PartialOrd for Card {
     fn partial_cmp(&self, other: &Card) -> Option<Ordering> {
-        Some(self.rank.cmp(&other.rank))
+        Some(self.cmp(other))
     }
 }
 
@@ -169,16 +383,19 @@ impl Card {
         }
     }
 
-    pub fn to_string(&self) -> String {
-        let mut output = "".to_owned();
-        let num_lines = LineNumber::iter().len();
-        for (i, line) in LineNumber::iter().enumerate() {
-            output.push_str(&self.to_single_string(line));
-            if i < num_lines - 1 {
-                output.push_str("\n");
-            }
-        }
-        output
+    /// Return the card in plain text, e.g. "QS" or "10H".
+    pub fn to_short_string(&self) -> String {
+        format!("{}{}", self.rank, self.suit.letter())
+    }
+
+    /// Read a card written by to_short_string.
+    pub fn from_short_string(s: &str) -> Option<Card> {
+        let split = s.len().checked_sub(1).filter(|&i| s.is_char_boundary(i))?;
+        let (rank, suit) = s.split_at(split);
+        Some(Card {
+            rank: Rank::from_symbol(rank)?,
+            suit: Suit::from_letter(suit)?,
+        })
     }
 
     pub fn get_all_with_rank(rank: Rank) -> Vec<Card> {
@@ -190,9 +407,67 @@ impl Card {
     }
 }
 
+// Ranks, suits and cards are stored in the same plain text the players
+// see, e.g. "10", "H" and "10H".
+#[cfg(feature = "serde")]
+impl Serialize for Rank {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Rank {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_with(deserializer, Rank::from_symbol, "rank")
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for Suit {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_char(self.letter())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Suit {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_with(deserializer, Suit::from_letter, "suit")
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for Card {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_short_string())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Card {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_with(deserializer, Card::from_short_string, "card")
+    }
+}
+
+#[cfg(feature = "serde")]
+fn deserialize_with<'de, D, T>(
+    deserializer: D,
+    parse: fn(&str) -> Option<T>,
+    kind: &str,
+) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = String::deserialize(deserializer)?;
+    parse(&s).ok_or_else(|| de::Error::custom(format!("invalid {} \"{}\"", kind, s)))
+}
+
 #[cfg(test)]
 mod test {
     use crate::card;
+    use strum::IntoEnumIterator;
     #[test]
     fn display_suit() {
         let suit = card::Suit::Hearts;
@@ -204,6 +479,75 @@ mod test {
         assert_eq!("Q", format!("{}", suit));
     }
     #[test]
+    fn suit_ordering() {
+        let suits: Vec<card::Suit> = card::Suit::iter().collect();
+        assert_eq!(
+            vec![
+                card::Suit::Clubs,
+                card::Suit::Spades,
+                card::Suit::Hearts,
+                card::Suit::Diamonds
+            ],
+            suits
+        );
+        let mut sorted = suits;
+        sorted.sort();
+        assert_eq!(
+            vec![
+                card::Suit::Clubs,
+                card::Suit::Diamonds,
+                card::Suit::Hearts,
+                card::Suit::Spades
+            ],
+            sorted
+        );
+    }
+    #[test]
+    fn names() {
+        assert_eq!("Queen", card::Rank::Queen.name());
+        assert_eq!("Sixes", card::Rank::Six.plural());
+        assert_eq!("Aces", card::Rank::Ace.plural());
+        assert_eq!("Hearts", card::Suit::Hearts.name());
+    }
+    #[test]
+    fn short_string() {
+        let card = card::Card {
+            rank: card::Rank::Ten,
+            suit: card::Suit::Hearts,
+        };
+        assert_eq!("10H", card.to_short_string());
+        assert_eq!(Some(card), card::Card::from_short_string("10H"));
+        assert_eq!(None, card::Card::from_short_string("1H"));
+        assert_eq!(None, card::Card::from_short_string(""));
+    }
+    #[test]
+    fn parse_rank() {
+        for text in &["Q", "q", "queen", "Queens", "12"] {
+            assert_eq!(Ok(card::Rank::Queen), text.parse());
+        }
+        assert_eq!(Ok(card::Rank::Six), "sixes".parse());
+        assert_eq!(Ok(card::Rank::Ten), "10".parse());
+        assert_eq!(Ok(card::Rank::Nine), "9s".parse());
+        assert_eq!(Ok(card::Rank::Ten), "10s".parse());
+        assert!("KS".parse::<card::Rank>().is_err());
+        assert!("15".parse::<card::Rank>().is_err());
+        assert!("queer".parse::<card::Rank>().is_err());
+    }
+    #[test]
+    fn parse_card() {
+        let card = card::Card {
+            rank: card::Rank::Queen,
+            suit: card::Suit::Spades,
+        };
+        for text in &["QS", "qs", "Q♠", "12S", "queen of spades", "Q spades"] {
+            assert_eq!(Ok(card), text.parse());
+        }
+        assert_eq!(Ok(card::Suit::Hearts), "Hearts".parse());
+        let error = "queen of spuds".parse::<card::Card>().unwrap_err();
+        assert_eq!("expected a suit such as H, ♥ or hearts", error.reason);
+        assert!("".parse::<card::Card>().is_err());
+    }
+    #[test]
     fn card_ordering() {
         let card1 = card::Card {
             rank: card::Rank::Three,
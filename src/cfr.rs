@@ -0,0 +1,506 @@
+use crate::card::{Card, Rank, Suit};
+use crate::game::GameMove;
+use crate::poker::{Commune, HandValue};
+use itertools::Itertools;
+use std::collections::HashMap;
+
+/// How many of the lowest legal raises a player may choose between.
+const RAISE_CHOICES: usize = 3;
+
+/// The most bets in a round. After that the player to act has to call.
+const MAX_BETS: usize = 5;
+
+/// A small, single round Commune game for two players: each is dealt
+/// `hand_size` cards from a deck holding every combination of `ranks` and
+/// `suits`, and they bet until one of them calls. The loser of the call
+/// scores -1 and the winner +1.
+#[derive(Clone, Debug)]
+pub struct SolverConfig {
+    pub ranks: Vec<Rank>,
+    pub suits: Vec<Suit>,
+    pub hand_size: usize,
+}
+
+/// Regrets and average strategy for one information set.
+#[derive(Clone, Debug)]
+struct Node {
+    actions: Vec<GameMove>,
+    regret_sum: Vec<f64>,
+    strategy_sum: Vec<f64>,
+}
+
+impl Node {
+    fn new(actions: Vec<GameMove>) -> Self {
+        let num_actions = actions.len();
+        Self {
+            actions,
+            regret_sum: vec![0.0; num_actions],
+            strategy_sum: vec![0.0; num_actions],
+        }
+    }
+
+    /// Return the current strategy by regret matching.
+    fn strategy(&self) -> Vec<f64> {
+        normalize(
+            self.regret_sum
+                .iter()
+                .map(|regret| regret.max(0.0))
+                .collect(),
+        )
+    }
+
+    fn average_strategy(&self) -> Vec<f64> {
+        normalize(self.strategy_sum.clone())
+    }
+}
+
+/// Scale `weights` to sum to one, or spread evenly if they are all zero.
+fn normalize(weights: Vec<f64>) -> Vec<f64> {
+    let total: f64 = weights.iter().sum();
+    if total > 0.0 {
+        weights.into_iter().map(|weight| weight / total).collect()
+    } else {
+        let num_weights = weights.len() as f64;
+        weights.iter().map(|_| 1.0 / num_weights).collect()
+    }
+}
+
+/// A counterfactual regret minimization solver that enumerates every deal
+/// on every iteration.
+#[derive(Clone, Debug)]
+pub struct Solver {
+    /// Every way to deal both hands, each sorted.
+    deals: Vec<[Vec<Card>; 2]>,
+    /// The HandValues worth betting: those some deal can contain.
+    values: Vec<HandValue>,
+    /// Whether each deal's commune contains each of `values`.
+    contains: Vec<Vec<bool>>,
+    nodes: HashMap<String, Node>,
+    pub iterations: usize,
+}
+
+impl Solver {
+    pub fn new(config: &SolverConfig) -> Self {
+        let deck: Vec<Card> = config
+            .suits
+            .iter()
+            .cartesian_product(config.ranks.iter())
+            .map(|(&suit, &rank)| Card { suit, rank })
+            .collect();
+        let mut deals = vec![];
+        for first in deck.iter().copied().combinations(config.hand_size) {
+            let rest: Vec<Card> = deck
+                .iter()
+                .copied()
+                .filter(|card| !first.contains(card))
+                .collect();
+            for second in rest.into_iter().combinations(config.hand_size) {
+                deals.push([sorted(first.clone()), sorted(second)]);
+            }
+        }
+        let communes: Vec<Commune> = deals.iter().map(commune).collect();
+        let values: Vec<HandValue> = HandValue::all_values()
+            .into_iter()
+            .filter(|value| {
+                communes
+                    .iter()
+                    .any(|commune| commune.contains_handvalue(*value))
+            })
+            .collect();
+        let contains = communes
+            .iter()
+            .map(|commune| {
+                values
+                    .iter()
+                    .map(|value| commune.contains_handvalue(*value))
+                    .collect()
+            })
+            .collect();
+        Self {
+            deals,
+            values,
+            contains,
+            nodes: HashMap::new(),
+            iterations: 0,
+        }
+    }
+
+    /// Run `iterations` more iterations of CFR over every deal.
+    pub fn train(&mut self, iterations: usize) {
+        for _ in 0..iterations {
+            for deal in 0..self.deals.len() {
+                self.cfr(deal, &mut vec![], [1.0, 1.0]);
+            }
+            self.iterations += 1;
+        }
+    }
+
+    /// Return the average strategy found so far.
+    pub fn strategy(&self) -> Strategy {
+        Strategy {
+            table: self
+                .nodes
+                .iter()
+                .map(|(key, node)| {
+                    let actions = node
+                        .actions
+                        .iter()
+                        .map(|action| action_label(*action))
+                        .zip(node.average_strategy())
+                        .collect();
+                    (key.clone(), actions)
+                })
+                .collect(),
+        }
+    }
+
+    /// Return the expected score of the first player when both play the
+    /// average strategy.
+    pub fn game_value(&self) -> f64 {
+        let weight = 1.0 / self.deals.len() as f64;
+        (0..self.deals.len())
+            .map(|deal| weight * self.average_value(deal, &mut vec![]))
+            .sum()
+    }
+
+    /// Return how much a best responder gains against the average strategy,
+    /// averaged over both seats. An equilibrium has exploitability 0.
+    pub fn exploitability(&self) -> f64 {
+        let weight = 1.0 / self.deals.len() as f64;
+        let mut total = 0.0;
+        for player in 0..2 {
+            let mut hands: Vec<&Vec<Card>> = vec![];
+            for deal in &self.deals {
+                if !hands.contains(&&deal[player]) {
+                    hands.push(&deal[player]);
+                }
+            }
+            for hand in hands {
+                let deals: Vec<(usize, f64)> = (0..self.deals.len())
+                    .filter(|&deal| self.deals[deal][player] == *hand)
+                    .map(|deal| (deal, weight))
+                    .collect();
+                total += self.best_response(player, &mut vec![], &deals);
+            }
+        }
+        total / 2.0
+    }
+
+    /// The moves available after `history`: the first player must bet, and
+    /// after that a player may call or raise. Only the lowest raises are
+    /// offered, and only for the first few bets, which keeps the tree small
+    /// enough to walk on every iteration.
+    fn actions(&self, history: &[HandValue]) -> Vec<GameMove> {
+        let mut actions = vec![];
+        if !history.is_empty() {
+            actions.push(GameMove::Call());
+        }
+        if history.len() >= MAX_BETS {
+            return actions;
+        }
+        actions.extend(
+            self.values
+                .iter()
+                .filter(|value| history.last().map_or(true, |bet| *value > bet))
+                .take(RAISE_CHOICES)
+                .map(|value| GameMove::Bet(*value)),
+        );
+        actions
+    }
+
+    /// Return the score of `player` when the player to act calls the last bet.
+    fn call_payoff(&self, deal: usize, history: &[HandValue], player: usize) -> f64 {
+        let caller = history.len() % 2;
+        let bet = history[history.len() - 1];
+        let bet = self
+            .values
+            .binary_search(&bet)
+            .expect("bets are taken from the values");
+        let caller_loses = self.contains[deal][bet];
+        let caller_score = if caller_loses { -1.0 } else { 1.0 };
+        if player == caller {
+            caller_score
+        } else {
+            -caller_score
+        }
+    }
+
+    /// Walk the game tree for one deal, updating regrets and the average
+    /// strategy, and return the first player's expected score.
+    fn cfr(&mut self, deal: usize, history: &mut Vec<HandValue>, reach: [f64; 2]) -> f64 {
+        let player = history.len() % 2;
+        let key = info_set_key(&self.deals[deal][player], history);
+        let actions = self.actions(history);
+        let strategy = self
+            .nodes
+            .entry(key.clone())
+            .or_insert_with(|| Node::new(actions.clone()))
+            .strategy();
+
+        let mut action_values = vec![0.0; actions.len()];
+        for (i, action) in actions.iter().enumerate() {
+            action_values[i] = match action {
+                GameMove::Bet(value) => {
+                    let mut next_reach = reach;
+                    next_reach[player] *= strategy[i];
+                    history.push(*value);
+                    let action_value = self.cfr(deal, history, next_reach);
+                    history.pop();
+                    action_value
+                }
+                _ => self.call_payoff(deal, history, 0),
+            };
+        }
+        let node_value: f64 = strategy
+            .iter()
+            .zip(&action_values)
+            .map(|(probability, value)| probability * value)
+            .sum();
+
+        // Values are from the first player's point of view.
+        let sign = if player == 0 { 1.0 } else { -1.0 };
+        let node = self.nodes.get_mut(&key).expect("node was just inserted");
+        for i in 0..actions.len() {
+            node.regret_sum[i] += reach[1 - player] * sign * (action_values[i] - node_value);
+            node.strategy_sum[i] += reach[player] * strategy[i];
+        }
+        node_value
+    }
+
+    fn average_strategy_at(&self, deal: usize, history: &[HandValue]) -> Vec<f64> {
+        let player = history.len() % 2;
+        let key = info_set_key(&self.deals[deal][player], history);
+        match self.nodes.get(&key) {
+            Some(node) => node.average_strategy(),
+            None => normalize(vec![0.0; self.actions(history).len()]),
+        }
+    }
+
+    fn average_value(&self, deal: usize, history: &mut Vec<HandValue>) -> f64 {
+        let strategy = self.average_strategy_at(deal, history);
+        let mut node_value = 0.0;
+        for (action, probability) in self.actions(history).into_iter().zip(strategy) {
+            node_value += probability
+                * match action {
+                    GameMove::Bet(value) => {
+                        history.push(value);
+                        let action_value = self.average_value(deal, history);
+                        history.pop();
+                        action_value
+                    }
+                    _ => self.call_payoff(deal, history, 0),
+                };
+        }
+        node_value
+    }
+
+    /// Return the best score `player` can reach against the average strategy,
+    /// when they hold the hand shared by every deal in `deals`. Each deal is
+    /// weighted by its chance times the opponent's probability of reaching
+    /// `history`.
+    fn best_response(
+        &self,
+        player: usize,
+        history: &mut Vec<HandValue>,
+        deals: &[(usize, f64)],
+    ) -> f64 {
+        let actions = self.actions(history);
+        let mut action_values = vec![0.0; actions.len()];
+        let to_act = history.len() % 2;
+        for (i, action) in actions.iter().enumerate() {
+            let weighted_deals: Vec<(usize, f64)> = if to_act == player {
+                deals.to_vec()
+            } else {
+                deals
+                    .iter()
+                    .map(|&(deal, weight)| {
+                        (deal, weight * self.average_strategy_at(deal, history)[i])
+                    })
+                    .collect()
+            };
+            action_values[i] = match action {
+                GameMove::Bet(value) => {
+                    history.push(*value);
+                    let action_value = self.best_response(player, history, &weighted_deals);
+                    history.pop();
+                    action_value
+                }
+                _ => weighted_deals
+                    .iter()
+                    .map(|&(deal, weight)| weight * self.call_payoff(deal, history, player))
+                    .sum(),
+            };
+        }
+        if to_act == player {
+            action_values.into_iter().fold(f64::NEG_INFINITY, f64::max)
+        } else {
+            action_values.into_iter().sum()
+        }
+    }
+}
+
+fn sorted(mut cards: Vec<Card>) -> Vec<Card> {
+    cards.sort_by_key(|card| (card.rank, card.suit));
+    cards
+}
+
+fn commune(deal: &[Vec<Card>; 2]) -> Commune {
+    Commune {
+        cards: deal.iter().flatten().copied().collect(),
+    }
+}
+
+/// Return the key of the information set of a player holding `hand` after
+/// the bets in `history`, e.g. "10H QS|1P 10,2P Q/10".
+pub fn info_set_key(hand: &[Card], history: &[HandValue]) -> String {
+    let cards: Vec<String> = sorted(hand.to_vec())
+        .iter()
+        .map(|card| card.to_short_string())
+        .collect();
+    let bets: Vec<String> = history.iter().map(|bet| bet.to_short_string()).collect();
+    format!("{}|{}", cards.join(" "), bets.join(","))
+}
+
+/// Return the name of a move in an exported strategy.
+pub fn action_label(action: GameMove) -> String {
+    match action {
+        GameMove::Bet(value) => value.to_short_string(),
+        GameMove::Call() => "call".to_owned(),
+        GameMove::NewGame(num_players) => format!("new {}", num_players),
+    }
+}
+
+/// A strategy table mapping each information set to the probability of
+/// every move available there.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Strategy {
+    pub table: HashMap<String, Vec<(String, f64)>>,
+}
+
+impl Strategy {
+    /// Return the probability of each named move for the information set.
+    pub fn get(&self, key: &str) -> Option<&Vec<(String, f64)>> {
+        self.table.get(key)
+    }
+
+    /// Write the strategy as text: one information set per line, followed by
+    /// tab separated "move=probability" pairs.
+    pub fn to_text(&self) -> String {
+        let mut output = String::new();
+        for key in self.table.keys().sorted() {
+            output.push_str(key);
+            for (action, probability) in &self.table[key] {
+                output.push_str(&format!("\t{}={}", action, probability));
+            }
+            output.push('\n');
+        }
+        output
+    }
+
+    /// Read a strategy written by to_text.
+    pub fn from_text(text: &str) -> Result<Self, String> {
+        let mut table = HashMap::new();
+        for (number, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let mut fields = line.split('\t');
+            let key = fields.next().unwrap_or_default().to_owned();
+            let mut actions = vec![];
+            for field in fields {
+                let (action, probability) = field
+                    .rsplit_once('=')
+                    .ok_or_else(|| format!("Line {}: expected move=probability.", number + 1))?;
+                let probability = probability
+                    .parse()
+                    .map_err(|_| format!("Line {}: bad probability.", number + 1))?;
+                actions.push((action.to_owned(), probability));
+            }
+            table.insert(key, actions);
+        }
+        Ok(Self { table })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::card;
+    use crate::cfr;
+    use crate::game;
+    use crate::poker;
+
+    fn small_config() -> cfr::SolverConfig {
+        cfr::SolverConfig {
+            ranks: vec![card::Rank::Jack, card::Rank::Queen, card::Rank::King],
+            suits: vec![card::Suit::Hearts, card::Suit::Spades],
+            hand_size: 1,
+        }
+    }
+
+    #[test]
+    fn deals_and_values() {
+        let solver = cfr::Solver::new(&small_config());
+        assert_eq!(30, solver.deals.len());
+        assert_eq!(6, solver.values.len());
+        assert_eq!(
+            vec![game::GameMove::Call()],
+            solver.actions(&[poker::HandValue::OnePair(card::Rank::King)])
+        );
+    }
+
+    #[test]
+    fn converges() {
+        let mut solver = cfr::Solver::new(&small_config());
+        solver.train(10);
+        let early = solver.exploitability();
+        solver.train(490);
+        let late = solver.exploitability();
+        assert!(late < early);
+        assert!(late < 0.05, "exploitability {}", late);
+        assert!(solver.game_value().abs() <= 1.0);
+    }
+
+    #[test]
+    fn trains_two_card_hands() {
+        let config = cfr::SolverConfig {
+            hand_size: 2,
+            ..small_config()
+        };
+        let mut solver = cfr::Solver::new(&config);
+        solver.train(1);
+        let early = solver.exploitability();
+        solver.train(19);
+        assert!(solver.exploitability() < early);
+        for (key, node) in &solver.nodes {
+            assert!(node.actions.len() <= cfr::RAISE_CHOICES + 1, "{}", key);
+        }
+        let longest = solver.values[..cfr::MAX_BETS].to_vec();
+        assert_eq!(vec![game::GameMove::Call()], solver.actions(&longest));
+    }
+
+    #[test]
+    fn info_set_key() {
+        let hand = vec![
+            card::Card {
+                rank: card::Rank::Queen,
+                suit: card::Suit::Spades,
+            },
+            card::Card {
+                rank: card::Rank::Ten,
+                suit: card::Suit::Hearts,
+            },
+        ];
+        let history = vec![poker::HandValue::OnePair(card::Rank::Ten)];
+        assert_eq!("10H QS|1P 10", cfr::info_set_key(&hand, &history));
+    }
+
+    #[test]
+    fn strategy_round_trip() {
+        let mut solver = cfr::Solver::new(&small_config());
+        solver.train(5);
+        let strategy = solver.strategy();
+        let text = strategy.to_text();
+        assert_eq!(Ok(strategy), cfr::Strategy::from_text(&text));
+        assert!(cfr::Strategy::from_text("key\tcall").is_err());
+    }
+}
@@ -1,24 +1,119 @@
+use crate::agent::PlayerView;
+use crate::analytics;
+use crate::card::Card;
 use crate::poker::{Commune, Deck, Hand, HandValue, PokerError};
+use crate::rules::{GameRules, RoundStarter, UndoRule};
+use rand::{thread_rng, Rng, SeedableRng};
+use rand_chacha::ChaChaRng;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::mem;
 use std::num::ParseIntError;
 
 pub type GameResult = Result<(), GameError>;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct Player {
     pub name: u8,
     pub hand: Hand,
     pub penalties: u8,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct GameState {
+    /// Every player who started the game, indexed by seat. Eliminated
+    /// players keep their seat and are skipped when passing the turn.
     pub players: Vec<Player>,
     pub current_turn: usize,
     pub current_bet: Option<HandValue>,
+    /// The bets made so far this round, in order, with the seat that made them.
+    pub round_bets: Vec<(usize, HandValue)>,
     pub deck: Deck,
+    pub phase: GamePhase,
+    pub rules: GameRules,
+    /// The seat that led the betting in the current round.
+    pub round_starter: usize,
+    /// Every shuffle in the game is drawn from a generator seeded with this
+    /// and the round, so replaying a seed deals the same hands in every round.
+    pub seed: u64,
+    /// The number of rounds dealt since the game started.
+    pub round: u64,
+    /// The bets and calls made since the game was dealt, oldest first. With
+    /// the seed, the rules and the number of players they reproduce every
+    /// state of the game.
+    pub history: Vec<GameMove>,
+    /// Moves taken back with undo, most recently undone last. Any new move
+    /// clears them.
+    pub undone: Vec<GameMove>,
 }
 
-#[derive(Debug)]
+/// Everything that decided a call, captured before the cards are redealt.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct CallOutcome {
+    pub caller: usize,
+    pub bettor: usize,
+    pub bet: HandValue,
+    /// The bets made during the round, in order, with the seat that made them.
+    pub round_bets: Vec<(usize, HandValue)>,
+    /// Every seat's hand at the time of the call. Eliminated seats hold none.
+    pub hands: Vec<Hand>,
+    /// The pooled cards that count towards the bet.
+    pub evidence: Vec<Card>,
+    pub bet_exists: bool,
+    pub penalized_player: usize,
+    pub eliminated: bool,
+    /// Every seat's penalties after the call.
+    pub penalties: Vec<u8>,
+}
+
+/// Something that happened in the game, reported by process_move in the
+/// order it happened.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub enum Event {
+    RoundStarted {
+        starter: usize,
+    },
+    /// One per active seat each round. The hand is private to the seat, so
+    /// front-ends should only show it to that player.
+    CardsDealt {
+        seat: usize,
+        hand: Hand,
+    },
+    BetPlaced {
+        seat: usize,
+        bet: HandValue,
+    },
+    CallMade {
+        seat: usize,
+        bet: HandValue,
+    },
+    CallResolved(CallOutcome),
+    PlayerPenalized {
+        seat: usize,
+        penalties: u8,
+    },
+    PlayerEliminated {
+        seat: usize,
+    },
+    GameWon {
+        winner: u8,
+    },
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub enum GamePhase {
+    InProgress,
+    Finished { winner: u8 },
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub enum GameMove {
     NewGame(u8),
     Bet(HandValue),
@@ -29,7 +124,47 @@ pub enum GameMove {
 pub enum GameError {
     Poker(PokerError),
     CallWithNoBet,
+    BetNotHigher(HandValue),
+    InvalidBet(HandValue),
+    InvalidRules(&'static str),
+    TooManyPlayers(usize),
+    NotEnoughPlayers,
+    GameOver,
     IO,
+    InvalidSave(String),
+    UnsupportedSaveVersion(u64),
+    CannotUndo(&'static str),
+    NothingToRedo,
+    /// An agent gave up its turn without moving, for example so that the
+    /// front-end can save the game. The turn is still theirs.
+    TurnInterrupted,
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameError::Poker(e) => write!(f, "{}", e),
+            GameError::CallWithNoBet => write!(f, "There is no bet to call."),
+            GameError::BetNotHigher(current_bet) => {
+                write!(f, "Bet must be higher than {}.", current_bet)
+            }
+            GameError::InvalidBet(value) => write!(f, "{} is not a possible hand.", value),
+            GameError::InvalidRules(reason) => write!(f, "{}", reason),
+            GameError::TooManyPlayers(max_players) => {
+                write!(f, "At most {} players fit in the deck.", max_players)
+            }
+            GameError::NotEnoughPlayers => write!(f, "At least two players are needed."),
+            GameError::GameOver => write!(f, "The game is over."),
+            GameError::IO => write!(f, "Could not read input."),
+            GameError::InvalidSave(reason) => write!(f, "The saved game is invalid: {}", reason),
+            GameError::UnsupportedSaveVersion(version) => {
+                write!(f, "Saved games of version {} are not supported.", version)
+            }
+            GameError::CannotUndo(reason) => write!(f, "{}", reason),
+            GameError::NothingToRedo => write!(f, "There is no move to redo."),
+            GameError::TurnInterrupted => write!(f, "The turn was interrupted."),
+        }
+    }
 }
 
 impl From<PokerError> for GameError {
@@ -39,46 +174,230 @@ impl From<PokerError> for GameError {
 }
 
 impl From<ParseIntError> for GameError {
-    fn from(e: ParseIntError) -> Self {
+    fn from(_: ParseIntError) -> Self {
         GameError::IO
     }
 }
 
 impl Player {
-    pub fn is_out(&self) -> bool {
-        self.penalties >= 3
+    pub fn is_out(&self, rules: &GameRules) -> bool {
+        self.penalties >= rules.max_penalties
     }
 }
 
 impl GameState {
-    pub fn init_game(num_players: u8) -> Self {
+    pub fn init_game(num_players: u8, rules: GameRules) -> Result<Self, GameError> {
+        Self::init_game_with_rng(num_players, rules, &mut thread_rng())
+    }
+
+    /// Start a game whose seed is drawn from the given random number generator.
+    pub fn init_game_with_rng<R: Rng + ?Sized>(
+        num_players: u8,
+        rules: GameRules,
+        rng: &mut R,
+    ) -> Result<Self, GameError> {
+        Self::init_game_with_seed(num_players, rules, rng.gen())
+    }
+
+    pub fn init_game_with_seed(
+        num_players: u8,
+        rules: GameRules,
+        seed: u64,
+    ) -> Result<Self, GameError> {
+        rules.validate()?;
         let mut new_game = Self {
             players: vec![],
             current_turn: 0,
             current_bet: None,
-            deck: Deck::get_full_deck(),
+            round_bets: vec![],
+            deck: Deck::get_ordered_decks(rules.num_decks),
+            phase: GamePhase::InProgress,
+            rules,
+            round_starter: 0,
+            seed,
+            round: 0,
+            history: vec![],
+            undone: vec![],
         };
-        new_game.create_new_game(num_players);
-        new_game
+        new_game.create_new_game(num_players, seed)?;
+        Ok(new_game)
     }
 
-    pub fn process_move(&mut self, game_move: GameMove) -> GameResult {
+    /// Play a move and return the events it caused.
+    pub fn process_move(&mut self, game_move: GameMove) -> Result<Vec<Event>, GameError> {
+        let mut events = vec![];
         match game_move {
-            GameMove::NewGame(num_players) => self.create_new_game(num_players)?,
-            GameMove::Bet(value) => self.process_bet(value),
-            GameMove::Call() => self.process_call()?,
+            GameMove::NewGame(num_players) => {
+                let seed = self.round_rng().gen();
+                self.create_new_game(num_players, seed)?;
+                events.extend(self.round_started_events());
+            }
+            _ if self.is_over() => return Err(GameError::GameOver),
+            GameMove::Bet(value) => {
+                let seat = self.current_turn;
+                let bet = self.process_bet(value)?;
+                self.history.push(GameMove::Bet(bet));
+                events.push(Event::BetPlaced { seat, bet });
+            }
+            GameMove::Call() => {
+                let outcome = self.process_call()?;
+                self.history.push(game_move);
+                let seat = outcome.penalized_player;
+                let penalties = outcome.penalties[seat];
+                let eliminated = outcome.eliminated;
+                events.push(Event::CallMade {
+                    seat: outcome.caller,
+                    bet: outcome.bet,
+                });
+                events.push(Event::CallResolved(outcome));
+                events.push(Event::PlayerPenalized { seat, penalties });
+                if eliminated {
+                    events.push(Event::PlayerEliminated { seat });
+                }
+                self.update_phase();
+                match self.phase {
+                    GamePhase::Finished { winner } => events.push(Event::GameWon { winner }),
+                    GamePhase::InProgress => events.extend(self.round_started_events()),
+                }
+            }
         };
-        Ok(())
+        self.undone.clear();
+        Ok(events)
+    }
+
+    /// Take back the last move, restoring the deck, bets and penalties to
+    /// exactly what they were before it. The move can be played again with
+    /// redo until another move is made.
+    pub fn undo(&mut self) -> Result<GameMove, GameError> {
+        match self.rules.undo {
+            UndoRule::Never => {
+                return Err(GameError::CannotUndo("Undo is turned off for this game."))
+            }
+            UndoRule::UntilNextMove if !self.undone.is_empty() => {
+                return Err(GameError::CannotUndo(
+                    "Only the last move can be taken back.",
+                ))
+            }
+            _ => (),
+        }
+        let game_move = *self
+            .history
+            .last()
+            .ok_or(GameError::CannotUndo("There is no move to undo."))?;
+        let mut previous = self.state_after(self.history.len() - 1)?;
+        previous.undone = mem::take(&mut self.undone);
+        previous.undone.push(game_move);
+        *self = previous;
+        Ok(game_move)
+    }
+
+    /// Play the move most recently taken back with undo.
+    pub fn redo(&mut self) -> Result<Vec<Event>, GameError> {
+        let game_move = *self.undone.last().ok_or(GameError::NothingToRedo)?;
+        let mut undone = mem::take(&mut self.undone);
+        let result = self.process_move(game_move);
+        if result.is_ok() {
+            undone.pop();
+        }
+        self.undone = undone;
+        result
+    }
+
+    /// Return the game as it was dealt, before any of its history.
+    pub fn initial_state(&self) -> Result<GameState, GameError> {
+        Self::init_game_with_seed(self.players.len() as u8, self.rules.clone(), self.seed)
+    }
+
+    /// Return the game as it was after the first `num_moves` moves of its
+    /// history. Playing on from it forks the game.
+    pub fn state_after(&self, num_moves: usize) -> Result<GameState, GameError> {
+        let mut state = self.initial_state()?;
+        for game_move in self.history.iter().take(num_moves) {
+            state.process_move(*game_move)?;
+        }
+        Ok(state)
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.phase != GamePhase::InProgress
+    }
+
+    /// Return the players that have not been eliminated, in seat order.
+    pub fn active_players(&self) -> impl Iterator<Item = &Player> {
+        self.players
+            .iter()
+            .filter(move |player| !player.is_out(&self.rules))
+    }
+
+    /// Return the number of cards held by every player still in the game.
+    pub fn num_cards_in_play(&self) -> usize {
+        self.active_players()
+            .map(|player| player.hand.cards.len())
+            .sum()
+    }
+
+    /// Return the probability that the current bet exists, as seen by the
+    /// player whose turn it is.
+    pub fn current_bet_probability(&self) -> Option<f64> {
+        let bet = self.current_bet?;
+        let hand = &self.players[self.current_turn].hand;
+        let num_unseen = self.num_cards_in_play() - hand.cards.len();
+        analytics::exact_probability(bet, hand, num_unseen, self.rules.num_decks).ok()
+    }
+
+    /// Return what the player in `seat` is allowed to know about the game.
+    pub fn player_view(&self, seat: usize) -> PlayerView {
+        PlayerView {
+            seat,
+            hand: self.players[seat].hand.clone(),
+            card_counts: self
+                .players
+                .iter()
+                .map(|player| player.hand.cards.len())
+                .collect(),
+            penalties: self.players.iter().map(|player| player.penalties).collect(),
+            current_bet: self.current_bet,
+            round_bets: self.round_bets.clone(),
+            rules: self.rules.clone(),
+        }
     }
 
     pub fn display(&self) {
-        for player in &self.players {
+        for player in self.active_players() {
             println!("Player {}: ", player.name);
-            println!("{}", player.hand.to_string());
+            println!("{}", player.hand);
         }
     }
 
-    fn create_new_game(&mut self, num_players: u8) -> GameResult {
+    /// Return the events announcing the round that was just dealt. A new
+    /// game is dealt by its constructor, so call this to hear about the
+    /// first round; process_move reports the later ones.
+    pub fn round_started_events(&self) -> Vec<Event> {
+        let mut events = vec![Event::RoundStarted {
+            starter: self.current_turn,
+        }];
+        for (seat, player) in self.players.iter().enumerate() {
+            if !player.is_out(&self.rules) {
+                events.push(Event::CardsDealt {
+                    seat,
+                    hand: player.hand.clone(),
+                });
+            }
+        }
+        events
+    }
+
+    /// Replace the game with a new one dealt from `seed`. The game is left
+    /// untouched if the players cannot be seated.
+    fn create_new_game(&mut self, num_players: u8, seed: u64) -> GameResult {
+        let deck = Deck::get_shuffled_decks(self.rules.num_decks, &mut round_rng(seed, 0));
+        let max_players = deck.len() / self.rules.max_hand_size();
+        if num_players as usize > max_players {
+            return Err(GameError::TooManyPlayers(max_players));
+        }
+        if num_players < 2 {
+            return Err(GameError::NotEnoughPlayers);
+        }
         let players = (0..num_players)
             .map(|name| Player {
                 name,
@@ -90,91 +409,206 @@ impl GameState {
             players,
             current_turn: 0,
             current_bet: None,
-            deck: Deck::get_full_deck(),
+            round_bets: vec![],
+            deck,
+            phase: GamePhase::InProgress,
+            rules: self.rules.clone(),
+            round_starter: 0,
+            seed,
+            round: 0,
+            history: vec![],
+            undone: vec![],
         };
         new_game.deal_hands()?;
         *self = new_game;
         Ok(())
     }
 
+    /// Return the generator for the current round. The game keeps no
+    /// generator state of its own, so it is fully described by its fields.
+    fn round_rng(&self) -> ChaChaRng {
+        round_rng(self.seed, self.round)
+    }
+
+    /// Reclaim every hand into the deck and deal a new round.
     fn deal_hands(&mut self) -> GameResult {
         for player in self.players.iter_mut() {
-            let num_cards = player.penalties + 1;
-            player.hand = self.deck.deal_cards(num_cards as usize)?;
+            let hand = mem::replace(&mut player.hand, Hand::empty_hand());
+            self.deck.return_cards(hand);
+        }
+        self.round += 1;
+        if self.rules.reshuffle {
+            self.deck.shuffle(&mut self.round_rng());
+        }
+        let rules = &self.rules;
+        for player in self
+            .players
+            .iter_mut()
+            .filter(|player| !player.is_out(rules))
+        {
+            player.hand = self.deck.deal_cards(rules.hand_size(player.penalties))?;
         }
         Ok(())
     }
 
-    fn process_bet(&mut self, value: HandValue) {
+    /// Place a bet and return it in normal form.
+    fn process_bet(&mut self, value: HandValue) -> Result<HandValue, GameError> {
+        let value = value.normalized();
+        if !value.is_valid() {
+            return Err(GameError::InvalidBet(value));
+        }
+        if let Some(current_bet) = self.current_bet {
+            if value <= current_bet {
+                return Err(GameError::BetNotHigher(current_bet));
+            }
+        }
         self.current_bet = Some(value);
+        self.round_bets.push((self.current_turn, value));
         self.increment_turn();
+        Ok(value)
     }
 
     fn increment_turn(&mut self) {
-        self.current_turn += 1;
-        if self.current_turn == self.players.len() {
-            self.current_turn = 0;
-        }
-    }
-
-    fn process_call(&mut self) -> GameResult {
-        match self.current_bet {
-            None => Err(GameError::CallWithNoBet),
-            Some(bet) => {
-                let penalized_player = if self.gather_all_cards().contains_handvalue(bet) {
-                    self.current_turn
-                } else {
-                    self.get_previous_player()
-                };
-                self.penalize_player(penalized_player);
-                self.current_turn = penalized_player;
-                self.current_bet = None;
-                self.deal_hands()
-            }
-        }
+        self.current_turn = self.get_next_player(self.current_turn);
     }
 
-    fn get_previous_player(&self) -> usize {
-        if self.current_turn > 0 {
-            self.current_turn - 1
+    /// Return the first seat after `seat` whose player is still in the game.
+    fn get_next_player(&self, seat: usize) -> usize {
+        let num_seats = self.players.len();
+        (1..=num_seats)
+            .map(|offset| (seat + offset) % num_seats)
+            .find(|&next| !self.players[next].is_out(&self.rules))
+            .unwrap_or(seat)
+    }
+
+    fn process_call(&mut self) -> Result<CallOutcome, GameError> {
+        let bet = self.current_bet.ok_or(GameError::CallWithNoBet)?;
+        let caller = self.current_turn;
+        let bettor = self.get_previous_player();
+        let commune = self.gather_all_cards();
+        let bet_exists = commune.contains_handvalue(bet);
+        let (penalized_player, winner) = if bet_exists {
+            (caller, bettor)
         } else {
-            self.players.len() - 1
+            (bettor, caller)
+        };
+        let hands = self
+            .players
+            .iter()
+            .map(|player| player.hand.clone())
+            .collect();
+        self.penalize_player(penalized_player);
+        if winner == caller && self.rules.shrink_on_successful_call {
+            let player = &mut self.players[caller];
+            player.penalties = player.penalties.saturating_sub(1);
         }
+        let eliminated = self.players[penalized_player].is_out(&self.rules);
+        self.round_starter = match self.rules.next_round_starter {
+            // The player after the loser starts if the penalty knocked them
+            // out.
+            RoundStarter::Loser if eliminated => self.get_next_player(penalized_player),
+            RoundStarter::Loser => penalized_player,
+            RoundStarter::Winner => winner,
+            RoundStarter::NextSeat => self.get_next_player(self.round_starter),
+        };
+        let outcome = CallOutcome {
+            caller,
+            bettor,
+            bet,
+            round_bets: mem::take(&mut self.round_bets),
+            hands,
+            evidence: commune.evidence(bet),
+            bet_exists,
+            penalized_player,
+            eliminated,
+            penalties: self.players.iter().map(|player| player.penalties).collect(),
+        };
+        self.current_turn = self.round_starter;
+        self.current_bet = None;
+        self.deal_hands()?;
+        Ok(outcome)
+    }
+
+    fn get_previous_player(&self) -> usize {
+        let num_seats = self.players.len();
+        (1..=num_seats)
+            .map(|offset| (self.current_turn + num_seats - offset) % num_seats)
+            .find(|&previous| !self.players[previous].is_out(&self.rules))
+            .unwrap_or(self.current_turn)
     }
 
     fn penalize_player(&mut self, player: usize) {
         self.players[player].penalties += 1;
-        if self.players[player].is_out() {
-            self.players.remove(player);
+        if self.players[player].is_out(&self.rules) {
+            let hand = mem::replace(&mut self.players[player].hand, Hand::empty_hand());
+            self.deck.return_cards(hand);
+        }
+    }
+
+    fn update_phase(&mut self) {
+        let active: Vec<&Player> = self.active_players().collect();
+        if let [winner] = active.as_slice() {
+            self.phase = GamePhase::Finished {
+                winner: winner.name,
+            };
         }
     }
 
     fn gather_all_cards(&self) -> Commune {
         Commune {
             cards: self
-                .players
-                .iter()
-                .flat_map(|player| player.hand.cards.iter().map(|card| *card))
+                .active_players()
+                .flat_map(|player| player.hand.cards.iter().copied())
                 .collect(),
         }
     }
 }
 
+/// Return the generator for a round of the game dealt from `seed`. ChaCha
+/// is used because, unlike StdRng, it deals the same cards on every platform
+/// and release of rand.
+fn round_rng(seed: u64, round: u64) -> ChaChaRng {
+    let mut key = [0; 32];
+    key[..8].copy_from_slice(&seed.to_le_bytes());
+    key[8..16].copy_from_slice(&round.to_le_bytes());
+    ChaChaRng::from_seed(key)
+}
+
 #[cfg(test)]
 mod test {
     use crate::card;
     use crate::game;
     use crate::poker;
+    use crate::rules;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
 
     fn default_gamestate() -> game::GameState {
         game::GameState {
             players: vec![],
             current_turn: 0,
             current_bet: None,
+            round_bets: vec![],
             deck: poker::Deck::get_full_deck(),
+            phase: game::GamePhase::InProgress,
+            rules: rules::GameRules::default(),
+            round_starter: 0,
+            seed: 0,
+            round: 0,
+            history: vec![],
+            undone: vec![],
         }
     }
 
+    fn total_cards(state: &game::GameState) -> usize {
+        state.deck.len()
+            + state
+                .players
+                .iter()
+                .map(|player| player.hand.cards.len())
+                .sum::<usize>()
+    }
+
     #[test]
     fn player_is_out() {
         let in_player = game::Player {
@@ -187,14 +621,15 @@ mod test {
             hand: poker::Hand::empty_hand(),
             penalties: 3,
         };
-        assert!(!in_player.is_out());
-        assert!(out_player.is_out());
+        let rules = rules::GameRules::default();
+        assert!(!in_player.is_out(&rules));
+        assert!(out_player.is_out(&rules));
     }
 
     #[test]
     fn new_game() {
         let mut state = default_gamestate();
-        state.create_new_game(3).unwrap();
+        state.create_new_game(3, 0).unwrap();
         assert_eq!(3, state.players.len());
         assert!(state
             .players
@@ -205,16 +640,54 @@ mod test {
     #[test]
     fn bet() {
         let mut state = default_gamestate();
-        state.create_new_game(3).unwrap();
+        state.create_new_game(3, 0).unwrap();
         state.current_turn = 2;
-        state.process_bet(poker::HandValue::ThreeOfAKind(card::Rank::Ten));
+        state
+            .process_bet(poker::HandValue::ThreeOfAKind(card::Rank::Ten))
+            .unwrap();
+        assert_eq!(0, state.current_turn);
+    }
+
+    #[test]
+    fn bet_not_higher() {
+        let mut state = default_gamestate();
+        state.create_new_game(3, 0).unwrap();
+        state
+            .process_bet(poker::HandValue::FourOfAKind(card::Rank::Ace))
+            .unwrap();
+        let lower = state.process_bet(poker::HandValue::OnePair(card::Rank::Two));
+        assert!(matches!(lower, Err(game::GameError::BetNotHigher(_))));
+        let equal = state.process_bet(poker::HandValue::FourOfAKind(card::Rank::Ace));
+        assert!(matches!(equal, Err(game::GameError::BetNotHigher(_))));
+        assert_eq!(
+            Some(poker::HandValue::FourOfAKind(card::Rank::Ace)),
+            state.current_bet
+        );
+        assert_eq!(1, state.current_turn);
+    }
+
+    #[test]
+    fn invalid_bet() {
+        let mut state = default_gamestate();
+        state.create_new_game(3, 0).unwrap();
+        let full_house = state.process_bet(poker::HandValue::FullHouse(
+            card::Rank::Queen,
+            card::Rank::Queen,
+        ));
+        assert!(matches!(full_house, Err(game::GameError::InvalidBet(_))));
+        let two_pair = state.process_bet(poker::HandValue::TwoPair(
+            card::Rank::Nine,
+            card::Rank::Nine,
+        ));
+        assert!(matches!(two_pair, Err(game::GameError::InvalidBet(_))));
+        assert_eq!(None, state.current_bet);
         assert_eq!(0, state.current_turn);
     }
 
     #[test]
     fn gather_all_cards() {
         let mut state = default_gamestate();
-        state.create_new_game(3).unwrap();
+        state.create_new_game(3, 0).unwrap();
         let gathered_cards = state.gather_all_cards().cards;
         assert!(state
             .players
@@ -226,7 +699,7 @@ mod test {
     #[test]
     fn unsuccessful_call() {
         let mut state = default_gamestate();
-        state.create_new_game(3).unwrap();
+        state.create_new_game(3, 0).unwrap();
         state.current_bet = Some(poker::HandValue::FourOfAKind(card::Rank::Ace));
         let penalized_player = 2;
         state.process_call().unwrap();
@@ -238,7 +711,7 @@ mod test {
     #[test]
     fn successful_call() {
         let mut state = default_gamestate();
-        state.create_new_game(3).unwrap();
+        state.create_new_game(3, 0).unwrap();
         let existing_rank = state.players[0].hand.cards[0].rank;
         state.current_bet = Some(poker::HandValue::HighCard(existing_rank));
         let penalized_player = 0;
@@ -247,4 +720,404 @@ mod test {
         assert_eq!(1, state.players[1].hand.cards.len());
         assert_eq!(1, state.players[2].hand.cards.len());
     }
+
+    #[test]
+    fn outcome_of_unsuccessful_call() {
+        let mut state = default_gamestate();
+        state.create_new_game(3, 0).unwrap();
+        state.current_bet = Some(poker::HandValue::FourOfAKind(card::Rank::Ace));
+        let outcome = state.process_call().unwrap();
+        assert_eq!(0, outcome.caller);
+        assert_eq!(2, outcome.bettor);
+        assert_eq!(2, outcome.penalized_player);
+        assert!(!outcome.bet_exists);
+        assert!(!outcome.eliminated);
+        assert!(outcome
+            .evidence
+            .iter()
+            .all(|card| card.rank == card::Rank::Ace));
+        assert_eq!(vec![0, 0, 1], outcome.penalties);
+    }
+
+    #[test]
+    fn outcome_of_successful_call() {
+        let mut state = default_gamestate();
+        state.create_new_game(3, 0).unwrap();
+        let existing_rank = state.players[0].hand.cards[0].rank;
+        state.current_bet = Some(poker::HandValue::HighCard(existing_rank));
+        let hand = state.players[0].hand.cards.clone();
+        let outcome = state.process_call().unwrap();
+        assert_eq!(0, outcome.penalized_player);
+        assert!(outcome.bet_exists);
+        assert_eq!(hand, outcome.hands[0].cards);
+        assert_eq!(1, outcome.evidence.len());
+        assert_eq!(existing_rank, outcome.evidence[0].rank);
+        assert_eq!(vec![1, 0, 0], outcome.penalties);
+    }
+
+    #[test]
+    fn too_many_players() {
+        let mut state = default_gamestate();
+        assert!(state.create_new_game(17, 0).is_ok());
+        let result = state.create_new_game(18, 0);
+        assert!(matches!(result, Err(game::GameError::TooManyPlayers(17))));
+        let result = state.create_new_game(1, 0);
+        assert!(matches!(result, Err(game::GameError::NotEnoughPlayers)));
+    }
+
+    #[test]
+    fn cards_reclaimed_each_round() {
+        let mut state = default_gamestate();
+        state.create_new_game(17, 0).unwrap();
+        while !state.is_over() {
+            state
+                .process_move(game::GameMove::Bet(poker::HandValue::RoyalFlush(
+                    card::Suit::Spades,
+                )))
+                .unwrap();
+            state.process_move(game::GameMove::Call()).unwrap();
+            assert_eq!(52, total_cards(&state));
+        }
+    }
+
+    #[test]
+    fn memory_deck_is_not_reshuffled() {
+        let mut state = default_gamestate();
+        state.rules.reshuffle = false;
+        state.create_new_game(2, 0).unwrap();
+        let first_round: Vec<card::Card> = state
+            .players
+            .iter()
+            .flat_map(|player| player.hand.cards.clone())
+            .collect();
+        state.current_turn = 1;
+        state.current_bet = Some(poker::HandValue::RoyalFlush(card::Suit::Spades));
+        state.process_call().unwrap();
+        assert_eq!(52, total_cards(&state));
+        assert!(state
+            .players
+            .iter()
+            .flat_map(|player| player.hand.cards.iter())
+            .all(|card| !first_round.contains(card)));
+    }
+
+    #[test]
+    fn game_over() {
+        let mut state = default_gamestate();
+        state.create_new_game(2, 0).unwrap();
+        state.players[0].penalties = state.rules.max_penalties - 1;
+        state.current_turn = 1;
+        state.current_bet = Some(poker::HandValue::RoyalFlush(card::Suit::Spades));
+        state.process_move(game::GameMove::Call()).unwrap();
+        assert_eq!(game::GamePhase::Finished { winner: 1 }, state.phase);
+        let result = state.process_move(game::GameMove::Bet(poker::HandValue::HighCard(
+            card::Rank::Two,
+        )));
+        assert!(matches!(result, Err(game::GameError::GameOver)));
+        state.process_move(game::GameMove::NewGame(2)).unwrap();
+        assert_eq!(game::GamePhase::InProgress, state.phase);
+    }
+
+    #[test]
+    fn eliminated_player_keeps_seat() {
+        let mut state = default_gamestate();
+        state.create_new_game(3, 0).unwrap();
+        state.players[1].penalties = state.rules.max_penalties - 1;
+        state.current_turn = 2;
+        state.current_bet = Some(poker::HandValue::RoyalFlush(card::Suit::Spades));
+        state.process_call().unwrap();
+        assert_eq!(3, state.players.len());
+        assert!(state.players[1].is_out(&state.rules));
+        assert!(state.players[1].hand.cards.is_empty());
+        assert_eq!(2, state.current_turn);
+        assert_eq!(0, state.get_previous_player());
+        state.increment_turn();
+        assert_eq!(0, state.current_turn);
+        assert_eq!(2, state.get_previous_player());
+        state.increment_turn();
+        assert_eq!(2, state.current_turn);
+    }
+
+    #[test]
+    fn eliminated_loser_in_last_seat() {
+        let mut state = default_gamestate();
+        state.create_new_game(3, 0).unwrap();
+        state.players[2].penalties = state.rules.max_penalties - 1;
+        state.current_turn = 0;
+        state.current_bet = Some(poker::HandValue::RoyalFlush(card::Suit::Spades));
+        state.process_call().unwrap();
+        assert!(state.players[2].is_out(&state.rules));
+        assert_eq!(0, state.current_turn);
+        assert_eq!(2, state.active_players().count());
+    }
+
+    #[test]
+    fn init_game_validates_rules() {
+        let bad_rules = rules::GameRules {
+            max_penalties: 0,
+            ..rules::GameRules::default()
+        };
+        let result = game::GameState::init_game(2, bad_rules);
+        assert!(matches!(result, Err(game::GameError::InvalidRules(_))));
+    }
+
+    #[test]
+    fn custom_hand_sizes() {
+        let mut state = default_gamestate();
+        state.rules.starting_hand_size = 2;
+        state.rules.cards_per_penalty = 3;
+        state.rules.num_decks = 2;
+        state.create_new_game(4, 0).unwrap();
+        assert_eq!(104, total_cards(&state));
+        state.current_turn = 1;
+        state.current_bet = Some(poker::HandValue::RoyalFlush(card::Suit::Spades));
+        state.process_call().unwrap();
+        assert_eq!(5, state.players[0].hand.cards.len());
+        assert_eq!(2, state.players[1].hand.cards.len());
+        assert_eq!(104, total_cards(&state));
+    }
+
+    #[test]
+    fn too_many_players_for_rules() {
+        let mut state = default_gamestate();
+        state.rules.starting_hand_size = 5;
+        state.rules.cards_per_penalty = 5;
+        // Hands grow to 15 cards, so only three players fit in one deck.
+        let result = state.create_new_game(4, 0);
+        assert!(matches!(result, Err(game::GameError::TooManyPlayers(3))));
+        state.rules.num_decks = 2;
+        assert!(state.create_new_game(4, 0).is_ok());
+    }
+
+    #[test]
+    fn shrink_on_successful_call() {
+        let mut state = default_gamestate();
+        state.rules.shrink_on_successful_call = true;
+        state.create_new_game(3, 0).unwrap();
+        state.players[1].penalties = 2;
+        state.current_turn = 1;
+        state.current_bet = Some(poker::HandValue::RoyalFlush(card::Suit::Spades));
+        state.process_call().unwrap();
+        assert_eq!(1, state.players[0].penalties);
+        assert_eq!(1, state.players[1].penalties);
+        assert_eq!(2, state.players[1].hand.cards.len());
+    }
+
+    #[test]
+    fn winner_starts_next_round() {
+        let mut state = default_gamestate();
+        state.rules.next_round_starter = rules::RoundStarter::Winner;
+        state.create_new_game(3, 0).unwrap();
+        state.current_turn = 2;
+        state.current_bet = Some(poker::HandValue::RoyalFlush(card::Suit::Spades));
+        state.process_call().unwrap();
+        assert_eq!(2, state.current_turn);
+    }
+
+    #[test]
+    fn next_seat_starts_next_round() {
+        let mut state = default_gamestate();
+        state.rules.next_round_starter = rules::RoundStarter::NextSeat;
+        state.create_new_game(3, 0).unwrap();
+        state.current_turn = 2;
+        state.current_bet = Some(poker::HandValue::RoyalFlush(card::Suit::Spades));
+        state.process_call().unwrap();
+        assert_eq!(1, state.current_turn);
+        state.current_bet = Some(poker::HandValue::RoyalFlush(card::Suit::Spades));
+        state.process_call().unwrap();
+        assert_eq!(2, state.current_turn);
+    }
+
+    fn all_hands(state: &game::GameState) -> Vec<Vec<card::Card>> {
+        state
+            .players
+            .iter()
+            .map(|player| player.hand.cards.clone())
+            .collect()
+    }
+
+    #[test]
+    fn seeded_games_repeat() {
+        let rules = rules::GameRules::default();
+        let mut first = game::GameState::init_game_with_seed(4, rules.clone(), 42).unwrap();
+        let mut second = game::GameState::init_game_with_seed(4, rules, 42).unwrap();
+        assert_eq!(42, first.seed);
+        for _ in 0..5 {
+            assert_eq!(all_hands(&first), all_hands(&second));
+            for state in [&mut first, &mut second].iter_mut() {
+                state
+                    .process_move(game::GameMove::Bet(poker::HandValue::RoyalFlush(
+                        card::Suit::Spades,
+                    )))
+                    .unwrap();
+                state.process_move(game::GameMove::Call()).unwrap();
+            }
+        }
+        first.process_move(game::GameMove::NewGame(4)).unwrap();
+        second.process_move(game::GameMove::NewGame(4)).unwrap();
+        assert_eq!(first.seed, second.seed);
+        assert_eq!(all_hands(&first), all_hands(&second));
+    }
+
+    #[test]
+    fn failed_new_game_keeps_state() {
+        let mut state =
+            game::GameState::init_game_with_seed(3, rules::GameRules::default(), 7).unwrap();
+        let bet = poker::HandValue::HighCard(card::Rank::Two);
+        state.process_move(game::GameMove::Bet(bet)).unwrap();
+        let hands = all_hands(&state);
+        assert!(state.process_move(game::GameMove::NewGame(1)).is_err());
+        assert!(state.process_move(game::GameMove::NewGame(200)).is_err());
+        assert_eq!(7, state.seed);
+        assert_eq!(Some(bet), state.current_bet);
+        assert_eq!(hands, all_hands(&state));
+    }
+
+    #[test]
+    fn init_game_with_rng() {
+        let rules = rules::GameRules::default();
+        let first =
+            game::GameState::init_game_with_rng(3, rules.clone(), &mut StdRng::seed_from_u64(1))
+                .unwrap();
+        let second =
+            game::GameState::init_game_with_rng(3, rules, &mut StdRng::seed_from_u64(1)).unwrap();
+        assert_eq!(first.seed, second.seed);
+        assert_eq!(all_hands(&first), all_hands(&second));
+    }
+
+    #[test]
+    fn current_bet_probability() {
+        let mut state = default_gamestate();
+        state.create_new_game(3, 0).unwrap();
+        assert_eq!(None, state.current_bet_probability());
+        let own_rank = state.players[0].hand.cards[0].rank;
+        state.current_bet = Some(poker::HandValue::HighCard(own_rank));
+        assert_eq!(Some(1.0), state.current_bet_probability());
+        state.current_bet = Some(poker::HandValue::Straight(card::Rank::Ace));
+        assert_eq!(Some(0.0), state.current_bet_probability());
+    }
+
+    #[test]
+    fn player_view() {
+        let mut state = default_gamestate();
+        state.create_new_game(3, 0).unwrap();
+        state
+            .process_bet(poker::HandValue::OnePair(card::Rank::Two))
+            .unwrap();
+        let view = state.player_view(1);
+        assert_eq!(1, view.seat);
+        assert_eq!(state.players[1].hand.cards, view.hand.cards);
+        assert_eq!(vec![1, 1, 1], view.card_counts);
+        assert_eq!(
+            vec![(0, poker::HandValue::OnePair(card::Rank::Two))],
+            view.round_bets
+        );
+        state.process_call().unwrap();
+        assert!(state.round_bets.is_empty());
+    }
+
+    #[test]
+    fn events() {
+        let mut state =
+            game::GameState::init_game_with_seed(2, rules::GameRules::default(), 3).unwrap();
+        let events = state.process_move(game::GameMove::NewGame(2)).unwrap();
+        assert_eq!(game::Event::RoundStarted { starter: 0 }, events[0]);
+        assert_eq!(
+            game::Event::CardsDealt {
+                seat: 1,
+                hand: state.players[1].hand.clone(),
+            },
+            events[2]
+        );
+
+        let bet = poker::HandValue::StraightFlush(card::Rank::Ace, card::Suit::Spades);
+        let events = state.process_move(game::GameMove::Bet(bet)).unwrap();
+        let bet = poker::HandValue::RoyalFlush(card::Suit::Spades);
+        assert_eq!(vec![game::Event::BetPlaced { seat: 0, bet }], events);
+
+        let events = state.process_move(game::GameMove::Call()).unwrap();
+        assert_eq!(game::Event::CallMade { seat: 1, bet }, events[0]);
+        assert!(matches!(&events[1], game::Event::CallResolved(outcome) if !outcome.bet_exists));
+        assert_eq!(
+            game::Event::PlayerPenalized {
+                seat: 0,
+                penalties: 1
+            },
+            events[2]
+        );
+        assert_eq!(game::Event::RoundStarted { starter: 0 }, events[3]);
+        assert_eq!(6, events.len());
+    }
+
+    #[test]
+    fn game_won_event() {
+        let mut state =
+            game::GameState::init_game_with_seed(2, rules::GameRules::default(), 3).unwrap();
+        state.players[0].penalties = 2;
+        let bet = poker::HandValue::RoyalFlush(card::Suit::Spades);
+        state.process_move(game::GameMove::Bet(bet)).unwrap();
+        let events = state.process_move(game::GameMove::Call()).unwrap();
+        assert_eq!(game::Event::PlayerEliminated { seat: 0 }, events[3]);
+        assert_eq!(game::Event::GameWon { winner: 1 }, events[4]);
+        assert_eq!(5, events.len());
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let mut state =
+            game::GameState::init_game_with_seed(3, rules::GameRules::default(), 7).unwrap();
+        let bet = poker::HandValue::ThreeOfAKind(card::Rank::Ace);
+        state.process_move(game::GameMove::Bet(bet)).unwrap();
+        let before_call = state.clone();
+        state.process_move(game::GameMove::Call()).unwrap();
+        let after_call = state.clone();
+
+        assert_eq!(game::GameMove::Call(), state.undo().unwrap());
+        assert_eq!(before_call.deck, state.deck);
+        assert_eq!(before_call.players, state.players);
+        assert_eq!(vec![game::GameMove::Call()], state.undone);
+        assert_eq!(game::GameMove::Bet(bet), state.undo().unwrap());
+        assert!(state.undo().is_err());
+
+        state.redo().unwrap();
+        state.redo().unwrap();
+        assert_eq!(after_call, state);
+        assert!(matches!(state.redo(), Err(game::GameError::NothingToRedo)));
+
+        state.undo().unwrap();
+        state.process_move(game::GameMove::Call()).unwrap();
+        assert!(state.undone.is_empty());
+    }
+
+    #[test]
+    fn rejected_move_keeps_redo() {
+        let mut state =
+            game::GameState::init_game_with_seed(3, rules::GameRules::default(), 7).unwrap();
+        let bet = poker::HandValue::OnePair(card::Rank::Ace);
+        state.process_move(game::GameMove::Bet(bet)).unwrap();
+        state.undo().unwrap();
+        assert!(state.process_move(game::GameMove::Call()).is_err());
+        assert_eq!(vec![game::GameMove::Bet(bet)], state.undone);
+        state.redo().unwrap();
+        assert_eq!(Some(bet), state.current_bet);
+    }
+
+    #[test]
+    fn undo_rules() {
+        let rules = rules::GameRules {
+            undo: rules::UndoRule::UntilNextMove,
+            ..Default::default()
+        };
+        let mut state = game::GameState::init_game_with_seed(2, rules, 7).unwrap();
+        let bet = poker::HandValue::HighCard(card::Rank::Two);
+        state.process_move(game::GameMove::Bet(bet)).unwrap();
+        state.process_move(game::GameMove::Call()).unwrap();
+        state.undo().unwrap();
+        assert!(state.undo().is_err());
+        state.redo().unwrap();
+        state.undo().unwrap();
+
+        state.rules.undo = rules::UndoRule::Never;
+        assert!(matches!(state.undo(), Err(game::GameError::CannotUndo(_))));
+    }
 }
@@ -0,0 +1,20 @@
+pub mod agent;
+pub mod analytics;
+pub mod args;
+pub mod bot;
+pub mod card;
+pub mod cfr;
+pub mod game;
+pub mod mcts;
+pub mod notation;
+pub mod poker;
+pub mod replay;
+pub mod rules;
+#[cfg(feature = "serde")]
+pub mod save;
+#[cfg(test)]
+mod testing;
+pub mod tournament;
+
+#[macro_use]
+extern crate strum_macros;
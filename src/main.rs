@@ -1,116 +1,553 @@
-mod card;
-mod game;
-mod poker;
-
-#[macro_use]
-extern crate strum_macros;
-
-use crate::card::{Rank, Suit};
-use crate::game::{GameError, GameMove, GameResult, GameState};
-use crate::poker::HandValue;
+use commune::agent::{self, Agent, PlayerView};
+use commune::analytics;
+use commune::game::{CallOutcome, Event, GameError, GameMove, GamePhase, GameResult, GameState};
+use commune::notation;
+use commune::poker::{Hand, HandValue};
+use commune::replay::Replay;
+use commune::rules::{GameRules, UndoRule};
+#[cfg(feature = "serde")]
+use commune::save;
+use commune::tournament::AgentSpec;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::env;
+use std::fs;
 use std::io;
+use std::process;
+use std::rc::Rc;
+
+const BOT_AGGRESSION: f64 = 0.3;
+const BOT_BLUFF_FREQUENCY: f64 = 0.1;
+/// Clears the terminal and moves the cursor to the top left.
+const CLEAR_SCREEN: &str = "\x1B[2J\x1B[1;1H";
 
 fn main() -> GameResult {
     println!("Welcome to Commune!");
-    let mut state = new_game()?;
-    game_loop(&mut state)?;
+    let hot_seat = env::args().any(|arg| arg == "--hotseat");
+    let args: Vec<String> = env::args().collect();
+    if args.get(1).map(String::as_str) == Some("replay") {
+        let path = args.get(2).ok_or(GameError::IO)?;
+        return replay_game(&load_or_exit(path), hot_seat);
+    }
+    let mut state = match parse_option("--resume")? {
+        Some(path) => load_or_exit(&path),
+        None => new_game(seed_option())?,
+    };
+    let mut seats = choose_seats(&state, hot_seat)?;
+    game_loop(&mut state, &mut seats, hot_seat)?;
     Ok(())
 }
 
-fn new_game() -> Result<GameState, GameError> {
+fn new_game(seed: Option<u64>) -> Result<GameState, GameError> {
+    let rules = GameRules {
+        undo: parse_undo_rule()?,
+        ..GameRules::default()
+    };
     println!("How many players?");
     let num_players = parse_players()?;
-    Ok(GameState::init_game(num_players))
+    match seed {
+        Some(seed) => GameState::init_game_with_seed(num_players, rules, seed),
+        None => GameState::init_game(num_players, rules),
+    }
+}
+
+/// Read `--undo anytime|until_next_move|never`. Competitive games should
+/// use never.
+fn parse_undo_rule() -> Result<UndoRule, GameError> {
+    match parse_option("--undo")?.as_deref() {
+        None | Some("anytime") => Ok(UndoRule::Anytime),
+        Some("until_next_move") => Ok(UndoRule::UntilNextMove),
+        Some("never") => Ok(UndoRule::Never),
+        Some(_) => Err(GameError::InvalidRules(
+            "--undo must be anytime, until_next_move or never.",
+        )),
+    }
+}
+
+/// Read the value of a command line option such as `--seed <number>`, if
+/// given.
+fn parse_option(name: &str) -> Result<Option<String>, GameError> {
+    let args: Vec<String> = env::args().collect();
+    match args.iter().position(|arg| arg == name) {
+        None => Ok(None),
+        Some(index) => match args.get(index + 1) {
+            None => Err(GameError::IO),
+            Some(value) => Ok(Some(value.clone())),
+        },
+    }
+}
+
+#[cfg(feature = "serde")]
+fn save_game(state: &GameState, path: &str) {
+    match fs::write(path, save::to_json(state)) {
+        Ok(()) => println!("Saved the game to {}", path),
+        Err(e) => println!("Could not save to {}: {}", path, e),
+    }
+}
+
+#[cfg(not(feature = "serde"))]
+fn save_game(_state: &GameState, _path: &str) {
+    println!("Saving needs the serde feature. Use export to write a hand history instead.");
+}
+
+fn export_game(state: &GameState, path: &str) -> GameResult {
+    match fs::write(path, notation::write(state)?) {
+        Ok(()) => println!("Wrote the hand history to {}", path),
+        Err(e) => println!("Could not write {}: {}", path, e),
+    }
+    Ok(())
+}
+
+/// Read `--seed <number>`, which deals the same game on every run.
+fn seed_option() -> Option<u64> {
+    let seed = match parse_option("--seed") {
+        Ok(None) => return None,
+        Ok(Some(seed)) => seed
+            .parse()
+            .map_err(|_| format!("--seed expects a number, not \"{}\".", seed)),
+        Err(_) => Err("--seed needs a number.".to_owned()),
+    };
+    Some(seed.unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(2);
+    }))
 }
 
-fn game_loop(state: &mut GameState) -> GameResult {
+/// Read `--bot <spec>`, which picks the computer players the same way as a
+/// tournament lineup entry, e.g. `--bot strategy:solved.txt`.
+fn bot_spec() -> AgentSpec {
+    let spec = match parse_option("--bot") {
+        Ok(Some(spec)) => spec.parse(),
+        Ok(None) => Ok(AgentSpec::Probability {
+            aggression: BOT_AGGRESSION,
+            bluff_frequency: BOT_BLUFF_FREQUENCY,
+        }),
+        Err(_) => Err("--bot needs an agent such as strategy:<file>.".to_owned()),
+    };
+    spec.unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(2);
+    })
+}
+
+fn load_or_exit(path: &str) -> GameState {
+    match load_game(path) {
+        Ok(state) => state,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+}
+
+fn load_game(path: &str) -> Result<GameState, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("Could not read {}: {}", path, e))?;
+    // Saves are JSON objects; anything else is read as a hand history.
+    if text.trim_start().starts_with('{') {
+        load_json(&text)
+    } else {
+        notation::parse(&text).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(feature = "serde")]
+fn load_json(text: &str) -> Result<GameState, String> {
+    save::from_json(text).map_err(|e| e.to_string())
+}
+
+#[cfg(not(feature = "serde"))]
+fn load_json(_text: &str) -> Result<GameState, String> {
+    Err("Loading saved games needs the serde feature.".to_owned())
+}
+
+/// Play until the players stop asking for rematches. In hot-seat mode the
+/// hands stay hidden until a call reveals them.
+fn game_loop(state: &mut GameState, seats: &mut Seats, hot_seat: bool) -> GameResult {
+    let mut seed = None;
     loop {
-        state.display();
-        println!("Current Bet: {:?}", state.current_bet);
-        println!(
-            "Player {} - What is your next move? (new, bet, call)",
-            state.players[state.current_turn].name
-        );
-        process_user_input(state)?;
+        if let GamePhase::Finished { winner } = state.phase {
+            println!("Player {} wins!", winner);
+            if !parse_rematch()? {
+                return Ok(());
+            }
+            let num_players = state.players.len() as u8;
+            state.process_move(GameMove::NewGame(num_players))?;
+        }
+        if seed != Some(state.seed) {
+            seed = Some(state.seed);
+            println!("Seed: {}", state.seed);
+        }
+        if !hot_seat {
+            show_table(state);
+        }
+        // In hot-seat mode the screen is cleared before the next turn, so
+        // errors and the results of commands wait for the player to read them.
+        let mut pause = false;
+        let result = match agent::play_turn(state, &mut seats.agents) {
+            Err(GameError::TurnInterrupted) => {
+                pause = true;
+                run_commands(state, seats)
+            }
+            result => result,
+        };
+        match result {
+            Err(GameError::IO) => return Err(GameError::IO),
+            Err(e) => {
+                println!("{}", e);
+                pause = true;
+            }
+            Ok(events) => show_events(&events, hot_seat && !pause)?,
+        }
+        if hot_seat && pause {
+            wait_for_enter("Press enter to continue.")?;
+        }
+        if seats.agents.len() != state.players.len() {
+            *seats = choose_seats(state, hot_seat)?;
+        }
     }
 }
 
-fn process_user_input(state: &mut GameState) -> GameResult {
-    let mut input = String::new();
-    match io::stdin().read_line(&mut input) {
-        Ok(n) => {
-            match input.trim() {
-                "new" => {
-                    let new_state = new_game()?;
-                    *state = new_state;
-                }
-                "bet" => {
-                    let handvalue = parse_handvalue()?;
-                    state.process_move(GameMove::Bet(handvalue))?;
+/// Carry out the commands queued by the people at the keyboard.
+fn run_commands(state: &mut GameState, seats: &Seats) -> Result<Vec<Event>, GameError> {
+    let mut events = vec![];
+    loop {
+        let command = seats.commands.borrow_mut().pop_front();
+        match command {
+            None => return Ok(events),
+            Some(Command::Save(path)) => save_game(state, &path),
+            Some(Command::Undo) => undo_moves(state, seats)?,
+            Some(Command::Redo) => events.extend(redo_moves(state, seats)?),
+            Some(Command::Export(path)) => export_game(state, &path)?,
+            Some(Command::Load(path)) => match load_game(&path) {
+                Ok(loaded) => {
+                    *state = loaded;
+                    println!("Loaded the game from {}", path);
                 }
-                "call" => {
-                    state.process_move(GameMove::Call())?;
+                Err(e) => println!("{}", e),
+            },
+        }
+    }
+}
+
+/// Take back moves until a human is to play again, so that undoing against
+/// bots returns to the human's previous turn. Nothing is taken back if that
+/// turn cannot be reached, for example when only the last move may be undone,
+/// since the bot would just play its reply again.
+fn undo_moves(state: &mut GameState, seats: &Seats) -> GameResult {
+    let mut previous = state.clone();
+    previous.undo()?;
+    let mut num_undone = 1;
+    while seats.is_bot(previous.current_turn) {
+        if previous.undo().is_err() {
+            return Err(GameError::CannotUndo(
+                "Your last move cannot be taken back now that a bot has played.",
+            ));
+        }
+        num_undone += 1;
+    }
+    *state = previous;
+    println!("Took back {} move(s).", num_undone);
+    Ok(())
+}
+
+/// Play undone moves again, including the bots' replies.
+fn redo_moves(state: &mut GameState, seats: &Seats) -> Result<Vec<Event>, GameError> {
+    let mut events = state.redo()?;
+    while seats.is_bot(state.current_turn) && !state.undone.is_empty() {
+        events.extend(state.redo()?);
+    }
+    Ok(events)
+}
+
+/// Print every hand and the current bet.
+fn show_table(state: &GameState) {
+    state.display();
+    match (state.current_bet, state.current_bet_probability()) {
+        (Some(bet), Some(probability)) => {
+            println!("Current Bet: {} (P(exists) = {:.2})", bet, probability)
+        }
+        (Some(bet), None) => println!("Current Bet: {}", bet),
+        (None, _) => println!("Current Bet: None"),
+    }
+}
+
+/// Step through a saved game move by move. Forking starts a live game from
+/// the move on screen.
+fn replay_game(game: &GameState, hot_seat: bool) -> GameResult {
+    let replay = Replay::new(game)?;
+    let mut step = 0;
+    loop {
+        println!("Move {} of {}", step, replay.len());
+        show_events(replay.events(step), false)?;
+        show_table(replay.state(step));
+        if step == replay.len() {
+            println!("End of the recorded game.");
+        }
+        println!("Press enter for the next move, or type back, fork or quit.");
+        let mut input = String::new();
+        match io::stdin().read_line(&mut input) {
+            Ok(0) | Err(_) => return Err(GameError::IO),
+            Ok(_) => match input.trim() {
+                "" => step = (step + 1).min(replay.len()),
+                "back" => step = step.saturating_sub(1),
+                "fork" => {
+                    let mut state = replay.fork(step);
+                    let mut seats = choose_seats(&state, hot_seat)?;
+                    return game_loop(&mut state, &mut seats, hot_seat);
                 }
+                "quit" => return Ok(()),
                 _ => println!("Invalid input!"),
+            },
+        }
+    }
+}
+
+/// Print what happened during a turn. Dealt hands are left to each
+/// player's own turn.
+fn show_events(events: &[Event], hot_seat: bool) -> GameResult {
+    for event in events {
+        match event {
+            Event::BetPlaced { seat, bet } => println!("Player {} bets {}", seat, bet),
+            Event::CallMade { seat, .. } => println!("Player {} calls", seat),
+            Event::CallResolved(outcome) => show_call_outcome(outcome),
+            Event::PlayerPenalized { seat, .. } => println!("Player {} takes a penalty.", seat),
+            Event::PlayerEliminated { seat } => println!("Player {} is out!", seat),
+            Event::RoundStarted { .. } | Event::CardsDealt { .. } | Event::GameWon { .. } => (),
+        }
+    }
+    if hot_seat
+        && events
+            .iter()
+            .any(|event| matches!(event, Event::CallMade { .. }))
+    {
+        wait_for_enter("Press enter to continue.")?;
+    }
+    Ok(())
+}
+
+/// Explain how a call was resolved: every hand, the cards that count
+/// towards the bet, and who was penalized for it.
+fn show_call_outcome(outcome: &CallOutcome) {
+    for (seat, bet) in &outcome.round_bets {
+        println!("Player {} bet {}", seat, bet);
+    }
+    for (seat, hand) in outcome.hands.iter().enumerate() {
+        if !hand.cards.is_empty() {
+            println!("Player {}: ", seat);
+            println!("{}", hand);
+        }
+    }
+    if outcome.evidence.is_empty() {
+        println!("No cards count towards {}.", outcome.bet);
+    } else {
+        println!("Cards towards {}:", outcome.bet);
+        let evidence = Hand {
+            cards: outcome.evidence.clone(),
+        };
+        println!("{}", evidence);
+    }
+    if outcome.bet_exists {
+        println!(
+            "{} is there, so Player {} was wrong to call.",
+            outcome.bet, outcome.caller
+        );
+    } else {
+        println!(
+            "{} is not there, so Player {} was right to call.",
+            outcome.bet, outcome.caller
+        );
+    }
+    let penalties: Vec<String> = outcome
+        .penalties
+        .iter()
+        .enumerate()
+        .map(|(seat, penalties)| format!("Player {}: {}", seat, penalties))
+        .collect();
+    println!("Penalties - {}", penalties.join(", "));
+}
+
+/// The agent playing each seat. People take the first seats and bots the
+/// rest.
+struct Seats {
+    agents: Vec<Box<dyn Agent>>,
+    num_humans: usize,
+    commands: Commands,
+}
+
+impl Seats {
+    fn is_bot(&self, seat: usize) -> bool {
+        seat >= self.num_humans
+    }
+}
+
+/// Commands given by the people at the keyboard, waiting for the game loop.
+type Commands = Rc<RefCell<VecDeque<Command>>>;
+
+/// What a human asked for on their turn, other than a move.
+enum Command {
+    Save(String),
+    Load(String),
+    /// Write the game as a plain-text hand history.
+    Export(String),
+    Undo,
+    Redo,
+}
+
+/// Ask how many seats are played by bots. Bots take the last seats.
+fn choose_seats(state: &GameState, hot_seat: bool) -> Result<Seats, GameError> {
+    println!("How many of them are bots?");
+    let num_bots = parse_players()? as usize;
+    let num_players = state.players.len();
+    let num_humans = num_players.saturating_sub(num_bots);
+    let bot = bot_spec();
+    let commands = Commands::default();
+    let agents = (0..num_players)
+        .map(|seat| -> Box<dyn Agent> {
+            if seat < num_humans {
+                Box::new(HumanAgent {
+                    hot_seat,
+                    commands: commands.clone(),
+                })
+            } else {
+                bot.build(state.seed.wrapping_add(seat as u64))
+            }
+        })
+        .collect();
+    Ok(Seats {
+        agents,
+        num_humans,
+        commands,
+    })
+}
+
+/// A player at the keyboard. In hot-seat mode the players share the screen,
+/// so it is cleared before each turn and only shows the current hand.
+/// Commands other than moves are queued for the game loop, and the turn is
+/// interrupted so that it can carry them out.
+struct HumanAgent {
+    hot_seat: bool,
+    commands: Commands,
+}
+
+impl HumanAgent {
+    fn show_private_view(view: &PlayerView) -> GameResult {
+        print!("{}", CLEAR_SCREEN);
+        wait_for_enter(&format!(
+            "Press enter when only Player {} is looking.",
+            view.seat
+        ))?;
+        print!("{}", CLEAR_SCREEN);
+        println!("Player {}: ", view.seat);
+        println!("{}", view.hand);
+        for (seat, bet) in &view.round_bets {
+            println!("Player {} bet {}", seat, bet);
+        }
+        match view.current_bet {
+            Some(bet) => {
+                let probability = analytics::exact_probability(
+                    bet,
+                    &view.hand,
+                    view.num_unseen_cards(),
+                    view.rules.num_decks,
+                )?;
+                println!("Current Bet: {} (P(exists) = {:.2})", bet, probability);
+            }
+            None => println!("Current Bet: None"),
+        }
+        Ok(())
+    }
+}
+
+impl Agent for HumanAgent {
+    fn choose_move(&mut self, view: &PlayerView) -> Result<GameMove, GameError> {
+        if self.hot_seat {
+            HumanAgent::show_private_view(view)?;
+        }
+        loop {
+            println!(
+                "Player {} - What is your next move? (new, bet, call, undo, redo, save <file>, \
+                 load <file>, export <file>)",
+                view.seat
+            );
+            let mut input = String::new();
+            let command = match io::stdin().read_line(&mut input) {
+                Ok(0) | Err(_) => return Err(GameError::IO),
+                Ok(_) => match input.trim().split_once(' ') {
+                    Some(("save", path)) => Command::Save(path.trim().to_owned()),
+                    Some(("load", path)) => Command::Load(path.trim().to_owned()),
+                    Some(("export", path)) => Command::Export(path.trim().to_owned()),
+                    Some(("bet", value)) => match value.parse() {
+                        Ok(value) => return Ok(GameMove::Bet(value)),
+                        Err(e) => {
+                            println!("{}", e);
+                            continue;
+                        }
+                    },
+                    _ => match input.trim() {
+                        "new" => {
+                            println!("How many players?");
+                            return Ok(GameMove::NewGame(parse_players()?));
+                        }
+                        "bet" => return Ok(GameMove::Bet(parse_handvalue()?)),
+                        "call" => return Ok(GameMove::Call()),
+                        "undo" => Command::Undo,
+                        "redo" => Command::Redo,
+                        _ => {
+                            println!("Invalid input!");
+                            continue;
+                        }
+                    },
+                },
             };
-            Ok(())
+            self.commands.borrow_mut().push_back(command);
+            return Err(GameError::TurnInterrupted);
         }
-        Err(error) => Err(GameError::IO),
     }
 }
 
-fn parse_players() -> Result<u8, GameError> {
+fn wait_for_enter(prompt: &str) -> GameResult {
+    println!("{}", prompt);
     let mut input = String::new();
     match io::stdin().read_line(&mut input) {
-        Ok(_) => {
-            let num = input.trim().parse()?;
-            Ok(num)
-        }
-        Err(_) => Err(GameError::IO),
+        Ok(0) | Err(_) => Err(GameError::IO),
+        Ok(_) => Ok(()),
     }
 }
 
-fn parse_handvalue() -> Result<HandValue, GameError> {
-    println!("Enter Your Bet (e.g. quad A):");
+fn parse_rematch() -> Result<bool, GameError> {
+    println!("Rematch? (y/n)");
     let mut input = String::new();
     match io::stdin().read_line(&mut input) {
-        Ok(_) => {
-            let vec: Vec<&str> = input.split(" ").collect();
-            let hand = vec[0].trim();
-            let rank_one = Rank::from_str(vec[1].trim());
-            let rank_two = if vec.len() == 3 {
-                Rank::from_str(vec[2].trim())
-            } else {
-                None
-            };
+        Ok(_) => Ok(input.trim() == "y"),
+        Err(_) => Err(GameError::IO),
+    }
+}
 
-            match rank_one {
-                None => Err(GameError::IO),
-                Some(rank) => {
-                    let handvalue = match hand {
-                        "high" => HandValue::HighCard(rank),
-                        "pair" => HandValue::OnePair(rank),
-                        "twopair" => {
-                            match rank_two {
-                                None => return Err(GameError::IO),
-                                Some(rank2) => HandValue::TwoPair(rank, rank2)
-                            }
-                        }
-                        "triple" => HandValue::ThreeOfAKind(rank),
-                        "straight" => HandValue::Straight(rank),
-                        "fullhouse" => {
-                            match rank_two {
-                                None => return Err(GameError::IO),
-                                Some(rank2) => HandValue::FullHouse(rank, rank2)
-                            }
-                        }
-                        "quad" => HandValue::FourOfAKind(rank),
-                        _ => return Err(GameError::IO),
-                    };
-                    Ok(handvalue)
-                }
-            }
+/// Ask for a number of players until the player types one that can be read.
+fn parse_players() -> Result<u8, GameError> {
+    loop {
+        let mut input = String::new();
+        match io::stdin().read_line(&mut input) {
+            Ok(0) | Err(_) => return Err(GameError::IO),
+            Ok(_) => match input.trim().parse() {
+                Ok(num) => return Ok(num),
+                Err(_) => println!("Enter a number, not \"{}\".", input.trim()),
+            },
+        }
+    }
+}
+
+/// Ask for a bet until the player types one that can be read.
+fn parse_handvalue() -> Result<HandValue, GameError> {
+    loop {
+        println!("Enter Your Bet (e.g. pair Q, two pair Q 9, boat Q 9, flush K H):");
+        let mut input = String::new();
+        match io::stdin().read_line(&mut input) {
+            Ok(0) | Err(_) => return Err(GameError::IO),
+            Ok(_) => match input.parse() {
+                Ok(value) => return Ok(value),
+                Err(e) => println!("{}", e),
+            },
         }
-        Err(error) => Err(GameError::IO),
     }
 }
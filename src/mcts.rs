@@ -0,0 +1,378 @@
+use crate::agent::{Agent, PlayerView};
+use crate::game::{GameError, GameMove, GameState};
+use crate::poker::{Commune, Deck, Hand, HandValue};
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaChaRng;
+use std::time::{Duration, Instant};
+
+/// How many of the lowest legal raises are searched, on top of the raises
+/// the acting player holds in their own hand.
+const RAISE_CHOICES: usize = 4;
+
+/// How many deals are tried before settling for one that makes an opponent
+/// a bluffer.
+const DEAL_ATTEMPTS: usize = 20;
+
+/// A computer player that searches the rest of the round with Monte Carlo
+/// tree search. Each iteration deals the unseen cards at random among the
+/// other players, then plays the round out on a copy of the game.
+#[derive(Clone, Debug)]
+pub struct MctsBot {
+    /// The number of deals searched per move.
+    pub iterations: usize,
+    /// Stop searching after this long, even if iterations remain.
+    pub time_limit: Option<Duration>,
+    /// How strongly the search prefers rarely tried moves.
+    pub exploration: f64,
+    /// Between 0 and 1. How readily the bot believes opponents bluff.
+    pub bluff_belief: f64,
+    rng: ChaChaRng,
+}
+
+/// Search statistics for one move out of a node.
+#[derive(Clone, Debug)]
+struct Edge {
+    game_move: GameMove,
+    visits: f64,
+    /// The summed rewards of the player making the move.
+    reward: f64,
+    /// How many times the move was legal when the node was visited.
+    availability: f64,
+    child: Option<usize>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct Node {
+    edges: Vec<Edge>,
+}
+
+impl MctsBot {
+    pub fn new(iterations: usize, seed: u64) -> Self {
+        Self {
+            iterations,
+            time_limit: None,
+            exploration: 0.7,
+            bluff_belief: 0.1,
+            rng: ChaChaRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Return a game that agrees with everything in the view, with the unseen
+    /// cards dealt at random. Deals where an opponent's bet is missing from
+    /// the commune are only kept with probability `bluff_belief`.
+    fn determinize(&mut self, view: &PlayerView) -> Result<GameState, GameError> {
+        let num_players = view.card_counts.len() as u8;
+        let mut state =
+            GameState::init_game_with_seed(num_players, view.rules.clone(), self.rng.gen())?;
+
+        let mut deck = Deck::get_ordered_decks(view.rules.num_decks);
+        let mut unseen = deck.deal_cards(deck.len())?.cards;
+        for card in &view.hand.cards {
+            if let Some(index) = unseen.iter().position(|unseen_card| unseen_card == card) {
+                unseen.remove(index);
+            }
+        }
+
+        for attempt in 1..=DEAL_ATTEMPTS {
+            unseen.shuffle(&mut self.rng);
+            let mut remaining = unseen.len();
+            for (seat, player) in state.players.iter_mut().enumerate() {
+                player.hand = if seat == view.seat {
+                    view.hand.clone()
+                } else {
+                    let count = view.card_counts[seat].min(remaining);
+                    remaining -= count;
+                    Hand {
+                        cards: unseen[remaining..remaining + count].to_vec(),
+                    }
+                };
+            }
+            let commune = Commune {
+                cards: state
+                    .players
+                    .iter()
+                    .flat_map(|player| player.hand.cards.iter().copied())
+                    .collect(),
+            };
+            let honest = view
+                .round_bets
+                .iter()
+                .filter(|(seat, _)| *seat != view.seat)
+                .all(|(_, bet)| commune.contains_handvalue(*bet));
+            if honest || attempt == DEAL_ATTEMPTS || self.rng.gen_bool(self.bluff_belief) {
+                unseen.truncate(remaining);
+                break;
+            }
+        }
+
+        for (seat, player) in state.players.iter_mut().enumerate() {
+            player.penalties = view.penalties[seat];
+        }
+        deck.return_cards(Hand { cards: unseen });
+        state.deck = deck;
+        state.current_turn = view.seat;
+        state.current_bet = view.current_bet;
+        state.round_bets = view.round_bets.clone();
+        Ok(state)
+    }
+
+    /// Run one iteration from the root: select and expand down the tree,
+    /// play the round out, and back the rewards up.
+    fn iterate(&mut self, tree: &mut Vec<Node>, mut state: GameState) {
+        let mut path: Vec<(usize, usize, usize)> = vec![];
+        let mut node = 0;
+        let mut expanded = false;
+        let rewards = loop {
+            let seat = state.current_turn;
+            let moves = candidate_moves(&state);
+            if moves.is_empty() {
+                break None;
+            }
+            let edge = if expanded {
+                // Past the tree, play the round out with the rollout policy.
+                None
+            } else {
+                Some(self.select(&mut tree[node], &moves))
+            };
+            let game_move = match edge {
+                Some(edge) => tree[node].edges[edge].game_move,
+                None => rollout_move(&state, &moves),
+            };
+            if let Some(edge) = edge {
+                path.push((node, edge, seat));
+                match tree[node].edges[edge].child {
+                    Some(child) => node = child,
+                    None => {
+                        tree.push(Node::default());
+                        let child = tree.len() - 1;
+                        tree[node].edges[edge].child = Some(child);
+                        node = child;
+                        expanded = true;
+                    }
+                }
+            }
+            let penalties: Vec<u8> = state
+                .players
+                .iter()
+                .map(|player| player.penalties)
+                .collect();
+            if state.process_move(game_move).is_err() {
+                break None;
+            }
+            if let GameMove::Call() = game_move {
+                // The round is over. Every player but the one penalized wins.
+                break Some(
+                    state
+                        .players
+                        .iter()
+                        .zip(penalties)
+                        .map(|(player, before)| if player.penalties > before { 0.0 } else { 1.0 })
+                        .collect::<Vec<f64>>(),
+                );
+            }
+        };
+
+        if let Some(rewards) = rewards {
+            for (node, edge, seat) in path {
+                let edge = &mut tree[node].edges[edge];
+                edge.visits += 1.0;
+                edge.reward += rewards[seat];
+            }
+        }
+    }
+
+    /// Pick the edge to follow out of a node, adding an edge for the first
+    /// untried move if there is one.
+    fn select(&mut self, node: &mut Node, moves: &[GameMove]) -> usize {
+        for &game_move in moves {
+            match node
+                .edges
+                .iter_mut()
+                .find(|edge| edge.game_move == game_move)
+            {
+                Some(edge) => edge.availability += 1.0,
+                None => {
+                    node.edges.push(Edge {
+                        game_move,
+                        visits: 0.0,
+                        reward: 0.0,
+                        availability: 1.0,
+                        child: None,
+                    });
+                    return node.edges.len() - 1;
+                }
+            }
+        }
+
+        let mut best_edge = 0;
+        let mut best_score = f64::NEG_INFINITY;
+        for (index, edge) in node.edges.iter().enumerate() {
+            if !moves.contains(&edge.game_move) {
+                continue;
+            }
+            let score = if edge.visits == 0.0 {
+                f64::INFINITY
+            } else {
+                edge.reward / edge.visits
+                    + self.exploration * (edge.availability.ln() / edge.visits).sqrt()
+            };
+            // Break ties at random so equal moves share the visits.
+            if score > best_score || (score == best_score && self.rng.gen_bool(0.5)) {
+                best_edge = index;
+                best_score = score;
+            }
+        }
+        best_edge
+    }
+}
+
+impl Agent for MctsBot {
+    fn choose_move(&mut self, view: &PlayerView) -> Result<GameMove, GameError> {
+        let start = Instant::now();
+        let mut tree = vec![Node::default()];
+        for _ in 0..self.iterations {
+            if self
+                .time_limit
+                .map_or(false, |limit| start.elapsed() >= limit)
+            {
+                break;
+            }
+            let state = self.determinize(view)?;
+            self.iterate(&mut tree, state);
+        }
+
+        // Play the most visited move.
+        let best = tree[0]
+            .edges
+            .iter()
+            .max_by(|a, b| a.visits.total_cmp(&b.visits))
+            .map(|edge| edge.game_move);
+        match (best, view.current_bet) {
+            (Some(game_move), _) => Ok(game_move),
+            (None, Some(_)) => Ok(GameMove::Call()),
+            (None, None) => Ok(GameMove::Bet(HandValue::all_values()[0])),
+        }
+    }
+}
+
+/// Return the moves searched for the player to act: a call if there is a
+/// bet, the lowest few raises, and any raise their own hand makes.
+fn candidate_moves(state: &GameState) -> Vec<GameMove> {
+    let hand = Commune {
+        cards: state.players[state.current_turn].hand.cards.clone(),
+    };
+    let mut moves = vec![];
+    if state.current_bet.is_some() {
+        moves.push(GameMove::Call());
+    }
+    let raises = HandValue::all_values()
+        .into_iter()
+        .filter(|value| state.current_bet.map_or(true, |bet| *value > bet));
+    for (index, raise) in raises.enumerate() {
+        if index < RAISE_CHOICES || hand.contains_handvalue(raise) {
+            moves.push(GameMove::Bet(raise));
+        }
+    }
+    moves
+}
+
+/// The move played past the end of the tree. Players see the whole sampled
+/// deal: they call a bet that is not there, and otherwise raise to the
+/// lowest HandValue that is, or call when there is none.
+fn rollout_move(state: &GameState, moves: &[GameMove]) -> GameMove {
+    let commune = Commune {
+        cards: state
+            .players
+            .iter()
+            .flat_map(|player| player.hand.cards.iter().copied())
+            .collect(),
+    };
+    if let Some(bet) = state.current_bet {
+        if !commune.contains_handvalue(bet) {
+            return GameMove::Call();
+        }
+    }
+    HandValue::all_values()
+        .into_iter()
+        .filter(|value| state.current_bet.map_or(true, |bet| *value > bet))
+        .find(|value| commune.contains_handvalue(*value))
+        .map(GameMove::Bet)
+        .unwrap_or(moves[0])
+}
+
+#[cfg(test)]
+mod test {
+    use crate::agent::{self, Agent};
+    use crate::card;
+    use crate::game;
+    use crate::mcts;
+    use crate::poker;
+    use crate::rules;
+    use crate::testing::{card, play_game, view};
+
+    #[test]
+    fn determinize() {
+        let mut bot = mcts::MctsBot::new(1, 3);
+        let hand = vec![
+            card(card::Rank::Ace, card::Suit::Spades),
+            card(card::Rank::Two, card::Suit::Clubs),
+        ];
+        let view = view(
+            hand.clone(),
+            Some(poker::HandValue::HighCard(card::Rank::King)),
+        );
+        let state = bot.determinize(&view).unwrap();
+        assert_eq!(hand, state.players[0].hand.cards);
+        assert_eq!(2, state.players[1].hand.cards.len());
+        assert_eq!(48, state.deck.len());
+        assert!(state.players[1]
+            .hand
+            .cards
+            .iter()
+            .all(|unseen| !hand.contains(unseen)));
+        assert_eq!(view.round_bets, state.round_bets);
+    }
+
+    #[test]
+    fn calls_an_impossible_bet() {
+        let mut bot = mcts::MctsBot::new(300, 1);
+        let hand = vec![
+            card(card::Rank::Two, card::Suit::Clubs),
+            card(card::Rank::Seven, card::Suit::Hearts),
+        ];
+        let bet = Some(poker::HandValue::FourOfAKind(card::Rank::Ace));
+        assert_eq!(
+            game::GameMove::Call(),
+            bot.choose_move(&view(hand, bet)).unwrap()
+        );
+    }
+
+    #[test]
+    fn raises_over_a_sure_bet() {
+        let mut bot = mcts::MctsBot::new(300, 1);
+        let hand = vec![
+            card(card::Rank::King, card::Suit::Clubs),
+            card(card::Rank::King, card::Suit::Hearts),
+        ];
+        let bet = Some(poker::HandValue::HighCard(card::Rank::King));
+        assert_eq!(
+            game::GameMove::Bet(poker::HandValue::OnePair(card::Rank::King)),
+            bot.choose_move(&view(hand, bet)).unwrap()
+        );
+    }
+
+    #[test]
+    fn bots_finish_a_game() {
+        let rules = rules::GameRules {
+            num_decks: 2,
+            shrink_on_successful_call: true,
+            ..Default::default()
+        };
+        let mut state = game::GameState::init_game_with_seed(3, rules, 4).unwrap();
+        let mut agents: Vec<Box<dyn agent::Agent>> = (0..3)
+            .map(|seat| Box::new(mcts::MctsBot::new(50, seat)) as Box<dyn Agent>)
+            .collect();
+        play_game(&mut state, &mut agents);
+    }
+}
@@ -0,0 +1,439 @@
+use crate::card::{Card, ParseError};
+use crate::game::{Event, GameError, GameMove, GamePhase, GameState};
+use crate::poker::HandValue;
+use crate::replay::Replay;
+use crate::rules::{GameRules, RoundStarter, UndoRule};
+use std::fmt;
+
+/// A line of a hand history that does not match the game it describes.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct NotationError {
+    /// Counted from 1.
+    pub line_number: usize,
+    pub line: String,
+    pub reason: String,
+}
+
+impl fmt::Display for NotationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Line {} \"{}\": {}",
+            self.line_number, self.line, self.reason
+        )
+    }
+}
+
+/// Write a game as a plain-text hand history, e.g.
+///
+/// ```text
+/// players 2
+/// seed 3
+/// rules max_penalties=3 starting_hand_size=1 ...
+/// deal 0: 5H
+/// deal 1: QC
+/// bet 0: high 5
+/// call 1
+/// # Five high is there: 5H
+/// penalty 1
+/// ```
+///
+/// Everything after a '#' is a comment.
+pub fn write(game: &GameState) -> Result<String, GameError> {
+    let rules = &game.rules;
+    let mut lines = vec![
+        format!("players {}", game.players.len()),
+        format!("seed {}", game.seed),
+        format!(
+            "rules max_penalties={} starting_hand_size={} cards_per_penalty={} num_decks={} \
+             shrink_on_successful_call={} next_round_starter={} reshuffle={} undo={}",
+            rules.max_penalties,
+            rules.starting_hand_size,
+            rules.cards_per_penalty,
+            rules.num_decks,
+            rules.shrink_on_successful_call,
+            write_round_starter(rules.next_round_starter),
+            rules.reshuffle,
+            write_undo_rule(rules.undo)
+        ),
+    ];
+    let replay = Replay::new(game)?;
+    for step in 0..=replay.len() {
+        for event in replay.events(step) {
+            match event {
+                Event::RoundStarted { .. } => lines.push(String::new()),
+                Event::CardsDealt { seat, hand } => {
+                    lines.push(format!("deal {}: {}", seat, write_cards(&hand.cards)))
+                }
+                Event::BetPlaced { seat, bet } => {
+                    lines.push(format!("bet {}: {}", seat, write_bet(*bet)))
+                }
+                Event::CallMade { seat, .. } => lines.push(format!("call {}", seat)),
+                Event::CallResolved(outcome) if outcome.bet_exists => lines.push(format!(
+                    "# {} is there: {}",
+                    outcome.bet,
+                    write_cards(&outcome.evidence)
+                )),
+                Event::CallResolved(outcome) => {
+                    lines.push(format!("# {} is not there", outcome.bet))
+                }
+                Event::PlayerPenalized { seat, penalties } => {
+                    lines.push(format!("penalty {} # {} in total", seat, penalties))
+                }
+                Event::PlayerEliminated { seat } => lines.push(format!("out {}", seat)),
+                Event::GameWon { winner } => lines.push(format!("winner {}", winner)),
+            }
+        }
+    }
+    Ok(lines.join("\n") + "\n")
+}
+
+/// Rebuild a game from a hand history by playing every move through
+/// process_move. Deals, penalties, eliminations and the winner are checked
+/// against the game, and the first line that does not fit is reported.
+pub fn parse(text: &str) -> Result<GameState, NotationError> {
+    let mut num_players = None;
+    let mut seed = None;
+    let mut rules = GameRules::default();
+    let mut state: Option<GameState> = None;
+    let mut last_events: Vec<Event> = vec![];
+
+    for (index, raw_line) in text.lines().enumerate() {
+        let error = |reason: String| NotationError {
+            line_number: index + 1,
+            line: raw_line.to_owned(),
+            reason,
+        };
+        let line = raw_line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (keyword, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let rest = rest.trim();
+
+        if state.is_none() {
+            match keyword {
+                "players" => {
+                    num_players = Some(rest.parse().map_err(|_| error(bad("player count")))?);
+                    continue;
+                }
+                "seed" => {
+                    seed = Some(rest.parse().map_err(|_| error(bad("seed")))?);
+                    continue;
+                }
+                "rules" => {
+                    rules = read_rules(rest).map_err(error)?;
+                    continue;
+                }
+                _ => state = Some(start_game(num_players, seed, &rules).map_err(error)?),
+            }
+        }
+        let game = state.as_mut().expect("the game has started");
+
+        match keyword {
+            "deal" => {
+                let (seat, cards) = read_seat_and(game, rest).map_err(error)?;
+                let cards = read_cards(cards).map_err(error)?;
+                let hand = &game.players[seat].hand.cards;
+                if sorted(cards) != sorted(hand.clone()) {
+                    return Err(error(format!(
+                        "Player {} was dealt {}",
+                        seat,
+                        write_cards(hand)
+                    )));
+                }
+            }
+            "bet" => {
+                let (seat, bet) = read_seat_and(game, rest).map_err(error)?;
+                check_turn(game, seat).map_err(error)?;
+                let bet = bet.parse().map_err(|e: ParseError| error(e.reason))?;
+                last_events = game
+                    .process_move(GameMove::Bet(bet))
+                    .map_err(|e| error(e.to_string()))?;
+            }
+            "call" => {
+                check_turn(game, read_seat(game, rest).map_err(error)?).map_err(error)?;
+                last_events = game
+                    .process_move(GameMove::Call())
+                    .map_err(|e| error(e.to_string()))?;
+            }
+            "penalty" | "out" => {
+                let seat = read_seat(game, rest).map_err(error)?;
+                let happened = last_events.iter().any(|event| match event {
+                    Event::PlayerPenalized {
+                        seat: penalized, ..
+                    } => keyword == "penalty" && *penalized == seat,
+                    Event::PlayerEliminated { seat: eliminated } => {
+                        keyword == "out" && *eliminated == seat
+                    }
+                    _ => false,
+                });
+                if !happened {
+                    let what = if keyword == "out" {
+                        "eliminated"
+                    } else {
+                        "penalized"
+                    };
+                    return Err(error(format!(
+                        "Player {} was not {} by the last move",
+                        seat, what
+                    )));
+                }
+            }
+            "winner" => {
+                // Seats are checked against the players, so they fit in a u8.
+                let seat = read_seat(game, rest).map_err(error)?;
+                if game.phase != (GamePhase::Finished { winner: seat as u8 }) {
+                    return Err(error(format!("Player {} has not won", seat)));
+                }
+            }
+            "players" | "seed" | "rules" => {
+                return Err(error(format!(
+                    "\"{}\" must come before the first move",
+                    keyword
+                )))
+            }
+            _ => return Err(error(format!("unknown line \"{}\"", keyword))),
+        }
+    }
+
+    match state {
+        Some(state) => Ok(state),
+        None => start_game(num_players, seed, &rules).map_err(|reason| NotationError {
+            line_number: text.lines().count(),
+            line: String::new(),
+            reason,
+        }),
+    }
+}
+
+fn bad(what: &str) -> String {
+    format!("invalid {}", what)
+}
+
+fn start_game(
+    num_players: Option<u8>,
+    seed: Option<u64>,
+    rules: &GameRules,
+) -> Result<GameState, String> {
+    let num_players = num_players.ok_or("the players line is missing")?;
+    let seed = seed.ok_or("the seed line is missing")?;
+    GameState::init_game_with_seed(num_players, rules.clone(), seed).map_err(|e| e.to_string())
+}
+
+fn check_turn(game: &GameState, seat: usize) -> Result<(), String> {
+    if seat == game.current_turn {
+        Ok(())
+    } else {
+        Err(format!("it is Player {}'s turn", game.current_turn))
+    }
+}
+
+/// Read a seat of the game.
+fn read_seat(game: &GameState, text: &str) -> Result<usize, String> {
+    let seat = text.parse().map_err(|_| bad("seat"))?;
+    if seat < game.players.len() {
+        Ok(seat)
+    } else {
+        Err(format!("there is no Player {}", seat))
+    }
+}
+
+/// Split "<seat>: <rest>".
+fn read_seat_and<'a>(game: &GameState, text: &'a str) -> Result<(usize, &'a str), String> {
+    let (seat, rest) = text.split_once(':').ok_or("expected \"<seat>: ...\"")?;
+    Ok((read_seat(game, seat.trim())?, rest.trim()))
+}
+
+fn write_cards(cards: &[Card]) -> String {
+    let cards: Vec<String> = cards.iter().map(Card::to_short_string).collect();
+    cards.join(" ")
+}
+
+fn read_cards(text: &str) -> Result<Vec<Card>, String> {
+    text.split_whitespace()
+        .map(|card| Card::from_short_string(card).ok_or(format!("invalid card \"{}\"", card)))
+        .collect()
+}
+
+fn sorted(mut cards: Vec<Card>) -> Vec<Card> {
+    cards.sort_by_key(|card| (card.rank, card.suit));
+    cards
+}
+
+/// Write a bet the way it is typed at the prompt, e.g. "fullhouse Q 9".
+/// HandValue's FromStr reads it back.
+pub fn write_bet(value: HandValue) -> String {
+    match value {
+        HandValue::HighCard(rank) => format!("high {}", rank),
+        HandValue::OnePair(rank) => format!("pair {}", rank),
+        HandValue::TwoPair(first, second) => format!("twopair {} {}", first, second),
+        HandValue::ThreeOfAKind(rank) => format!("triple {}", rank),
+        HandValue::Straight(rank) => format!("straight {}", rank),
+        HandValue::Flush(rank, suit) => format!("flush {} {}", rank, suit.letter()),
+        HandValue::FullHouse(three_of, two_of) => format!("fullhouse {} {}", three_of, two_of),
+        HandValue::FourOfAKind(rank) => format!("quad {}", rank),
+        HandValue::StraightFlush(rank, suit) => {
+            format!("straightflush {} {}", rank, suit.letter())
+        }
+        HandValue::RoyalFlush(suit) => format!("royal {}", suit.letter()),
+    }
+}
+
+fn write_round_starter(starter: RoundStarter) -> &'static str {
+    match starter {
+        RoundStarter::Loser => "loser",
+        RoundStarter::Winner => "winner",
+        RoundStarter::NextSeat => "next_seat",
+    }
+}
+
+fn write_undo_rule(undo: UndoRule) -> &'static str {
+    match undo {
+        UndoRule::Anytime => "anytime",
+        UndoRule::UntilNextMove => "until_next_move",
+        UndoRule::Never => "never",
+    }
+}
+
+/// Read "name=value" settings over the default rules.
+fn read_rules(text: &str) -> Result<GameRules, String> {
+    let mut rules = GameRules::default();
+    for setting in text.split_whitespace() {
+        let (name, value) = setting
+            .split_once('=')
+            .ok_or(format!("expected name=value, not \"{}\"", setting))?;
+        let invalid = || format!("invalid value for {}", name);
+        match name {
+            "max_penalties" => rules.max_penalties = value.parse().map_err(|_| invalid())?,
+            "starting_hand_size" => {
+                rules.starting_hand_size = value.parse().map_err(|_| invalid())?
+            }
+            "cards_per_penalty" => {
+                rules.cards_per_penalty = value.parse().map_err(|_| invalid())?
+            }
+            "num_decks" => rules.num_decks = value.parse().map_err(|_| invalid())?,
+            "shrink_on_successful_call" => {
+                rules.shrink_on_successful_call = value.parse().map_err(|_| invalid())?
+            }
+            "next_round_starter" => {
+                rules.next_round_starter = match value {
+                    "loser" => RoundStarter::Loser,
+                    "winner" => RoundStarter::Winner,
+                    "next_seat" => RoundStarter::NextSeat,
+                    _ => return Err(invalid()),
+                }
+            }
+            "reshuffle" => rules.reshuffle = value.parse().map_err(|_| invalid())?,
+            "undo" => {
+                rules.undo = match value {
+                    "anytime" => UndoRule::Anytime,
+                    "until_next_move" => UndoRule::UntilNextMove,
+                    "never" => UndoRule::Never,
+                    _ => return Err(invalid()),
+                }
+            }
+            _ => return Err(format!("unknown rule \"{}\"", name)),
+        }
+    }
+    Ok(rules)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::card;
+    use crate::game;
+    use crate::notation;
+    use crate::poker;
+    use crate::rules;
+
+    fn played_game() -> game::GameState {
+        let rules = rules::GameRules {
+            max_penalties: 2,
+            next_round_starter: rules::RoundStarter::Winner,
+            undo: rules::UndoRule::Never,
+            ..Default::default()
+        };
+        let mut state = game::GameState::init_game_with_seed(2, rules, 3).unwrap();
+        while !state.is_over() {
+            let bet = poker::HandValue::OnePair(card::Rank::Two);
+            state.process_move(game::GameMove::Bet(bet)).unwrap();
+            state.process_move(game::GameMove::Call()).unwrap();
+        }
+        state
+    }
+
+    #[test]
+    fn round_trip() {
+        let state = played_game();
+        let text = notation::write(&state).unwrap();
+        assert!(text.starts_with("players 2\nseed 3\n"));
+        assert!(text.contains("\nbet 0: pair 2\ncall 1\n"));
+        assert!(text.contains("next_round_starter=winner"));
+        assert!(text.contains("undo=never"));
+        assert!(text.ends_with("winner 1\n"));
+        assert_eq!(Ok(state), notation::parse(&text));
+    }
+
+    #[test]
+    fn reports_the_first_illegal_line() {
+        let text = notation::write(&played_game()).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        let call = lines.iter().position(|line| *line == "call 1").unwrap();
+
+        let mut wrong_seat = lines.clone();
+        wrong_seat[call] = "call 0";
+        let error = notation::parse(&wrong_seat.join("\n")).unwrap_err();
+        assert_eq!(call + 1, error.line_number);
+        assert_eq!("it is Player 1's turn", error.reason);
+
+        let mut lower_bet = lines.clone();
+        lower_bet.insert(call, "bet 1: high 3");
+        let error = notation::parse(&lower_bet.join("\n")).unwrap_err();
+        assert_eq!(call + 1, error.line_number);
+
+        let deal = lines
+            .iter()
+            .position(|line| line.starts_with("deal 0"))
+            .unwrap();
+        let mut wrong_deal = lines.clone();
+        wrong_deal[deal] = "deal 0: AS";
+        let error = notation::parse(&wrong_deal.join("\n")).unwrap_err();
+        assert_eq!(deal + 1, error.line_number);
+    }
+
+    #[test]
+    fn rejects_unknown_seats() {
+        for line in &[
+            "deal 9: AS",
+            "bet 9: high 2",
+            "call 9",
+            "penalty 9",
+            "out 9",
+            "winner 257",
+        ] {
+            let text = format!("players 2\nseed 1\n{}\n", line);
+            let error = notation::parse(&text).unwrap_err();
+            assert_eq!(3, error.line_number);
+            assert!(error.reason.starts_with("there is no Player"), "{}", line);
+        }
+    }
+
+    #[test]
+    fn hand_written_history() {
+        let text = "players 3 # friday lunch\nseed 8\nbet 0: twopair 2 3\ncall 1\n";
+        let state = notation::parse(text).unwrap();
+        assert_eq!(2, state.history.len());
+        let error = notation::parse("seed 8\nbet 0: high 2\n").unwrap_err();
+        assert_eq!(2, error.line_number);
+        assert_eq!("the players line is missing", error.reason);
+        assert!(notation::parse("players 2\nseed 1\nfold 0\n").is_err());
+    }
+
+    #[test]
+    fn bets() {
+        for value in poker::HandValue::all_values() {
+            assert_eq!(Ok(value), notation::write_bet(value).parse());
+        }
+    }
+}
@@ -1,31 +1,280 @@
-use crate::card::{Card, LineNumber, Rank, Suit};
+use crate::card::{Card, LineNumber, ParseError, Rank, Suit};
 use itertools::Itertools;
 use rand::seq::SliceRandom;
-use rand::thread_rng;
+use rand::{thread_rng, Rng};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::fmt;
+use std::str::FromStr;
 use strum::IntoEnumIterator;
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub enum HandValue {
     HighCard(Rank),
     OnePair(Rank),
     TwoPair(Rank, Rank),
     ThreeOfAKind(Rank),
     Straight(Rank),
+    Flush(Rank, Suit),
     FullHouse(Rank, Rank),
     FourOfAKind(Rank),
+    StraightFlush(Rank, Suit),
+    RoyalFlush(Suit),
 }
 
-#[derive(Clone, Debug)]
+impl HandValue {
+    /// Return True iff the HandValue describes a hand that can actually exist.
+    pub fn is_valid(&self) -> bool {
+        match *self {
+            HandValue::TwoPair(first, second) => first != second,
+            HandValue::FullHouse(three_of, two_of) => three_of != two_of,
+            HandValue::Straight(top_rank) | HandValue::StraightFlush(top_rank, _) => {
+                top_rank >= Rank::Five
+            }
+            HandValue::Flush(top_rank, _) => top_rank >= Rank::Six,
+            _ => true,
+        }
+    }
+
+    /// Return the HandValue with its ranks in canonical order, so that
+    /// equivalent bets compare as equal.
+    pub fn normalized(self) -> HandValue {
+        match self {
+            HandValue::TwoPair(first, second) if first < second => {
+                HandValue::TwoPair(second, first)
+            }
+            HandValue::StraightFlush(Rank::Ace, suit) => HandValue::RoyalFlush(suit),
+            _ => self,
+        }
+    }
+
+    /// Return every distinct HandValue that can be bet, from lowest to highest.
+    pub fn all_values() -> Vec<HandValue> {
+        let mut values = vec![];
+        for rank in Rank::iter() {
+            values.push(HandValue::HighCard(rank));
+            values.push(HandValue::OnePair(rank));
+            values.push(HandValue::ThreeOfAKind(rank));
+            values.push(HandValue::Straight(rank));
+            values.push(HandValue::FourOfAKind(rank));
+            for other in Rank::iter() {
+                values.push(HandValue::TwoPair(rank, other));
+                values.push(HandValue::FullHouse(rank, other));
+            }
+            for suit in Suit::iter() {
+                values.push(HandValue::Flush(rank, suit));
+                values.push(HandValue::StraightFlush(rank, suit));
+            }
+        }
+        for suit in Suit::iter() {
+            values.push(HandValue::RoyalFlush(suit));
+        }
+        let mut values: Vec<HandValue> = values
+            .into_iter()
+            .filter(|value| value.is_valid() && value.normalized() == *value)
+            .collect();
+        values.sort();
+        values
+    }
+
+    /// Return a compact form of the HandValue for tight spaces, e.g.
+    /// "FH Q/9". FromStr reads it back.
+    pub fn to_short_string(self) -> String {
+        match self {
+            HandValue::HighCard(rank) => format!("HC {}", rank),
+            HandValue::OnePair(rank) => format!("1P {}", rank),
+            HandValue::TwoPair(first, second) => format!("2P {}/{}", first, second),
+            HandValue::ThreeOfAKind(rank) => format!("3K {}", rank),
+            HandValue::Straight(rank) => format!("ST {}", rank),
+            HandValue::Flush(rank, suit) => format!("FL {}{}", rank, suit.letter()),
+            HandValue::FullHouse(three_of, two_of) => format!("FH {}/{}", three_of, two_of),
+            HandValue::FourOfAKind(rank) => format!("4K {}", rank),
+            HandValue::StraightFlush(rank, suit) => format!("SF {}{}", rank, suit.letter()),
+            HandValue::RoyalFlush(suit) => format!("RF {}", suit.letter()),
+        }
+    }
+}
+
+impl fmt::Display for HandValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            HandValue::HighCard(rank) => write!(f, "{} high", rank.name()),
+            HandValue::OnePair(rank) => write!(f, "Pair of {}", rank.plural()),
+            HandValue::TwoPair(first, second) => {
+                write!(f, "Two pair, {} and {}", first.plural(), second.plural())
+            }
+            HandValue::ThreeOfAKind(rank) => write!(f, "Three {}", rank.plural()),
+            HandValue::Straight(rank) => write!(f, "Straight to the {}", rank.name()),
+            HandValue::Flush(rank, suit) => {
+                write!(f, "Flush in {} to the {}", suit.name(), rank.name())
+            }
+            HandValue::FullHouse(three_of, two_of) => {
+                write!(f, "{} full of {}", three_of.plural(), two_of.plural())
+            }
+            HandValue::FourOfAKind(rank) => write!(f, "Four {}", rank.plural()),
+            HandValue::StraightFlush(rank, suit) => write!(
+                f,
+                "Straight flush in {} to the {}",
+                suit.name(),
+                rank.name()
+            ),
+            HandValue::RoyalFlush(suit) => write!(f, "Royal flush in {}", suit.name()),
+        }
+    }
+}
+
+/// The names players give each kind of hand, with the keyword used for it
+/// when bets are typed at the prompt.
+const HAND_NAMES: &[(&str, &str)] = &[
+    ("high", "high"),
+    ("high card", "high"),
+    ("pair", "pair"),
+    ("one pair", "pair"),
+    ("twopair", "twopair"),
+    ("two pair", "twopair"),
+    ("two pairs", "twopair"),
+    ("triple", "triple"),
+    ("trips", "triple"),
+    ("set", "triple"),
+    ("three of a kind", "triple"),
+    ("straight", "straight"),
+    ("flush", "flush"),
+    ("fullhouse", "fullhouse"),
+    ("full house", "fullhouse"),
+    ("boat", "fullhouse"),
+    ("quad", "quad"),
+    ("quads", "quad"),
+    ("four of a kind", "quad"),
+    ("straightflush", "straightflush"),
+    ("straight flush", "straightflush"),
+    ("royal", "royal"),
+    ("royal flush", "royal"),
+    // The compact forms written by HandValue::to_short_string.
+    ("hc", "high"),
+    ("1p", "pair"),
+    ("2p", "twopair"),
+    ("3k", "triple"),
+    ("st", "straight"),
+    ("fl", "flush"),
+    ("fh", "fullhouse"),
+    ("4k", "quad"),
+    ("sf", "straightflush"),
+    ("rf", "royal"),
+];
+
+/// Words that read naturally between the parts of a bet but carry nothing,
+/// as in "two pair, queens and nines".
+const FILLER_WORDS: &[&str] = &["of", "to", "the", "and", "over"];
+
+/// Reads a bet typed by a player, ignoring case: the name of the hand
+/// followed by its ranks and then its suit, e.g. "pair Q", "two pair queens
+/// and nines", "boat Q 9", "trips 12" or "flush K hearts".
+impl FromStr for HandValue {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.to_lowercase();
+        let words: Vec<&str> = lower
+            .split(|c: char| c.is_whitespace() || c == ',' || c == '/')
+            .filter(|word| !word.is_empty())
+            .collect();
+        let (name_length, kind) = HAND_NAMES
+            .iter()
+            .map(|(name, kind)| (name.split(' ').collect::<Vec<&str>>(), *kind))
+            .filter(|(name, _)| words.starts_with(name))
+            .map(|(name, kind)| (name.len(), kind))
+            .max()
+            .ok_or_else(|| {
+                ParseError::new(
+                    s,
+                    "expected a hand such as \"pair Q\", \"two pair Q 9\" or \"boat Q 9\"",
+                )
+            })?;
+
+        let mut ranks = vec![];
+        let mut suits = vec![];
+        for word in &words[name_length..] {
+            if FILLER_WORDS.contains(word) {
+                continue;
+            }
+            // A flush's rank and suit may be written as one card, as in "FL KH".
+            // Only hands with a suit read "9s" as a card rather than nines.
+            if matches!(kind, "flush" | "straightflush") {
+                if let Some(card) = Card::from_short_string(&word.to_uppercase()) {
+                    ranks.push(card.rank);
+                    suits.push(card.suit);
+                    continue;
+                }
+            }
+            match (word.parse(), word.parse(), word.parse::<Card>()) {
+                (Ok(rank), _, _) => ranks.push(rank),
+                (_, Ok(suit), _) => suits.push(suit),
+                (_, _, Ok(card)) => {
+                    ranks.push(card.rank);
+                    suits.push(card.suit);
+                }
+                _ => {
+                    return Err(ParseError::new(
+                        s,
+                        format!("\"{}\" is not a rank or a suit", word),
+                    ))
+                }
+            }
+        }
+
+        let value = match (kind, ranks.as_slice(), suits.as_slice()) {
+            ("high", [rank], []) => HandValue::HighCard(*rank),
+            ("pair", [rank], []) => HandValue::OnePair(*rank),
+            ("twopair", [first, second], []) => HandValue::TwoPair(*first, *second),
+            ("triple", [rank], []) => HandValue::ThreeOfAKind(*rank),
+            ("straight", [rank], []) => HandValue::Straight(*rank),
+            ("flush", [rank], [suit]) => HandValue::Flush(*rank, *suit),
+            ("fullhouse", [three_of, two_of], []) => HandValue::FullHouse(*three_of, *two_of),
+            ("quad", [rank], []) => HandValue::FourOfAKind(*rank),
+            ("straightflush", [rank], [suit]) => HandValue::StraightFlush(*rank, *suit),
+            ("royal", [], [suit]) => HandValue::RoyalFlush(*suit),
+            _ => {
+                let example = match kind {
+                    "high" => "high Q",
+                    "pair" => "pair Q",
+                    "twopair" => "two pair Q 9",
+                    "triple" => "trips Q",
+                    "straight" => "straight 9",
+                    "flush" => "flush Q H",
+                    "fullhouse" => "full house Q 9",
+                    "quad" => "quads Q",
+                    "straightflush" => "straight flush 9 H",
+                    _ => "royal H",
+                };
+                return Err(ParseError::new(
+                    s,
+                    format!("expected a bet like \"{}\"", example),
+                ));
+            }
+        };
+        Ok(value)
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct Hand {
     pub cards: Vec<Card>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct Deck {
     cards: Vec<Card>,
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct Commune {
     pub cards: Vec<Card>,
 }
@@ -35,22 +284,30 @@ pub enum PokerError {
     NotEnoughCards(String),
 }
 
+impl fmt::Display for PokerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PokerError::NotEnoughCards(message) => write!(f, "{}", message),
+        }
+    }
+}
+
 impl Hand {
     /// Return an empty hand.
     pub fn empty_hand() -> Hand {
         Hand { cards: vec![] }
     }
+}
 
-    pub fn to_string(&self) -> String {
-        let mut output = "".to_owned();
+impl fmt::Display for Hand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         for i in LineNumber::iter() {
             for card in &self.cards {
-                output.push_str(&card.to_single_string(i));
-                output.push_str(" ");
+                write!(f, "{} ", card.to_single_string(i))?;
             }
-            output.push_str("\n");
+            writeln!(f)?;
         }
-        output
+        Ok(())
     }
 }
 
@@ -58,18 +315,17 @@ impl Commune {
     /// Return True iff the Commune contains the input HandValue.
     pub fn contains_handvalue(&self, value: HandValue) -> bool {
         match value {
+            HandValue::RoyalFlush(suit) => self.contains_straight_flush(Rank::Ace, suit),
+            HandValue::StraightFlush(top_rank, suit) => {
+                self.contains_straight_flush(top_rank, suit)
+            }
             HandValue::FourOfAKind(rank) => self.contains_x_cards_of_rank(4, rank),
             HandValue::FullHouse(three_of, two_of) => {
                 self.contains_handvalue(HandValue::ThreeOfAKind(three_of))
                     && self.contains_handvalue(HandValue::OnePair(two_of))
             }
-            HandValue::Straight(top_rank) => {
-                if top_rank < Rank::Six {
-                    false
-                } else {
-                    self.contains_straight(top_rank)
-                }
-            }
+            HandValue::Straight(top_rank) => self.contains_straight(top_rank),
+            HandValue::Flush(top_rank, suit) => self.contains_flush(top_rank, suit),
             HandValue::ThreeOfAKind(rank) => self.contains_x_cards_of_rank(3, rank),
             HandValue::TwoPair(first, second) => {
                 self.contains_x_cards_of_rank(2, first) && self.contains_x_cards_of_rank(2, second)
@@ -79,43 +335,166 @@ impl Commune {
         }
     }
 
-    fn contains_x_cards_of_rank(&self, x: u8, rank: Rank) -> bool {
-        let needed_cards = Card::get_all_with_rank(rank);
-        let num_cards = needed_cards.iter().fold(0, |acc, card| {
-            acc + if self.cards.contains(card) { 1 } else { 0 }
-        });
-        num_cards >= x
+    /// Return the cards that count towards the HandValue. If the Commune
+    /// contains it these make it up; otherwise they are the part that exists.
+    pub fn evidence(&self, value: HandValue) -> Vec<Card> {
+        match value {
+            HandValue::RoyalFlush(suit) => self.evidence(HandValue::StraightFlush(Rank::Ace, suit)),
+            HandValue::StraightFlush(top_rank, suit) => Self::ranks_in_straight(top_rank)
+                .unwrap_or_default()
+                .into_iter()
+                .flat_map(|rank| self.take_cards(1, |card| *card == Card { suit, rank }))
+                .collect(),
+            HandValue::FourOfAKind(rank) => self.take_cards(4, |card| card.rank == rank),
+            HandValue::FullHouse(three_of, two_of) => {
+                let mut cards = self.take_cards(3, |card| card.rank == three_of);
+                cards.extend(self.take_cards(2, |card| card.rank == two_of));
+                cards
+            }
+            HandValue::Straight(top_rank) => Self::ranks_in_straight(top_rank)
+                .unwrap_or_default()
+                .into_iter()
+                .flat_map(|rank| self.take_cards(1, |card| card.rank == rank))
+                .collect(),
+            HandValue::Flush(top_rank, suit) => {
+                let mut cards = self.take_cards(1, |card| {
+                    *card
+                        == Card {
+                            suit,
+                            rank: top_rank,
+                        }
+                });
+                let mut lower_cards =
+                    self.take_cards(usize::MAX, |card| card.suit == suit && card.rank < top_rank);
+                lower_cards.sort_by_key(|card| Reverse(card.rank));
+                cards.extend(lower_cards.into_iter().take(4));
+                cards
+            }
+            HandValue::ThreeOfAKind(rank) => self.take_cards(3, |card| card.rank == rank),
+            HandValue::TwoPair(first, second) => {
+                let mut cards = self.take_cards(2, |card| card.rank == first);
+                cards.extend(self.take_cards(2, |card| card.rank == second));
+                cards
+            }
+            HandValue::OnePair(rank) => self.take_cards(2, |card| card.rank == rank),
+            HandValue::HighCard(rank) => self.take_cards(1, |card| card.rank == rank),
+        }
+    }
+
+    /// Return up to x of the cards matching the predicate.
+    fn take_cards<P: Fn(&Card) -> bool>(&self, x: usize, predicate: P) -> Vec<Card> {
+        self.cards
+            .iter()
+            .filter(|card| predicate(card))
+            .take(x)
+            .copied()
+            .collect()
+    }
+
+    fn contains_x_cards_of_rank(&self, x: usize, rank: Rank) -> bool {
+        self.cards.iter().filter(|card| card.rank == rank).count() >= x
     }
 
     fn contains_straight(&self, top_rank: Rank) -> bool {
-        let top_rank_index = top_rank.to_u8() as usize;
-        let all_ranks: Vec<Rank> = Rank::iter().collect();
-        let ranks_in_straight = &all_ranks[top_rank_index - 6..top_rank_index - 1];
-        let all_possible_cards_in_straight: Vec<Vec<Card>> = ranks_in_straight
+        match Self::ranks_in_straight(top_rank) {
+            None => false,
+            Some(ranks) => ranks.into_iter().all(|rank| {
+                Card::get_all_with_rank(rank)
+                    .iter()
+                    .any(|card| self.cards.contains(card))
+            }),
+        }
+    }
+
+    fn contains_straight_flush(&self, top_rank: Rank, suit: Suit) -> bool {
+        match Self::ranks_in_straight(top_rank) {
+            None => false,
+            Some(ranks) => ranks
+                .into_iter()
+                .all(|rank| self.cards.contains(&Card { suit, rank })),
+        }
+    }
+
+    /// A flush to top_rank needs the top card itself plus four lower cards
+    /// of the same suit.
+    fn contains_flush(&self, top_rank: Rank, suit: Suit) -> bool {
+        let num_lower_cards = self
+            .cards
             .iter()
-            .map(|rank| Card::get_all_with_rank(*rank))
-            .collect();
-        all_possible_cards_in_straight
-            .into_iter()
-            .all(|cards_in_rank| {
-                cards_in_rank
-                    .into_iter()
-                    .any(|card| self.cards.contains(&card))
-            })
+            .filter(|card| card.suit == suit && card.rank < top_rank)
+            .count();
+        self.cards.contains(&Card {
+            suit,
+            rank: top_rank,
+        }) && num_lower_cards >= 4
+    }
+
+    /// Return the five ranks of the straight ending at top_rank, or None if
+    /// no straight ends there. The ace plays low in the wheel (A-2-3-4-5).
+    pub(crate) fn ranks_in_straight(top_rank: Rank) -> Option<Vec<Rank>> {
+        let mut ranks: Vec<Rank> = Rank::iter().filter(|rank| *rank <= top_rank).collect();
+        if top_rank == Rank::Five {
+            ranks.insert(0, Rank::Ace);
+        }
+        if ranks.len() < 5 {
+            None
+        } else {
+            Some(ranks.split_off(ranks.len() - 5))
+        }
     }
 }
 
 impl Deck {
     /// Return a standard, shuffled 52 card deck.
     pub fn get_full_deck() -> Self {
-        let mut cards: Vec<Card> = Suit::iter()
-            .cartesian_product(Rank::iter())
+        Self::get_multiple_decks(1)
+    }
+
+    /// Return num_decks standard decks shuffled together.
+    pub fn get_multiple_decks(num_decks: u8) -> Self {
+        Self::get_shuffled_decks(num_decks, &mut thread_rng())
+    }
+
+    /// Return num_decks standard decks shuffled together with the given
+    /// random number generator, so that a seeded generator always produces
+    /// the same order.
+    pub fn get_shuffled_decks<R: Rng + ?Sized>(num_decks: u8, rng: &mut R) -> Self {
+        let mut deck = Self::get_ordered_decks(num_decks);
+        deck.shuffle(rng);
+        deck
+    }
+
+    /// Return num_decks standard decks, sorted by suit and then by rank.
+    pub fn get_ordered_decks(num_decks: u8) -> Self {
+        let cards = (0..num_decks)
+            .flat_map(|_| Suit::iter().cartesian_product(Rank::iter()))
             .map(|(suit, rank)| Card { suit, rank })
             .collect();
-        let mut rng = thread_rng();
-        cards.shuffle(&mut rng);
+        Self { cards }
+    }
+
+    /// Return the cards left in the deck, with the next card to be dealt last.
+    pub fn cards(&self) -> &[Card] {
+        &self.cards
+    }
+
+    /// Return the number of cards left in the deck.
+    pub fn len(&self) -> usize {
+        self.cards.len()
+    }
 
-        Self { cards: cards }
+    pub fn is_empty(&self) -> bool {
+        self.cards.is_empty()
+    }
+
+    /// Shuffle the cards left in the deck.
+    pub fn shuffle<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        self.cards.shuffle(rng);
+    }
+
+    /// Put the cards of a hand back at the bottom of the deck.
+    pub fn return_cards(&mut self, hand: Hand) {
+        self.cards.splice(0..0, hand.cards);
     }
 
     /// Deal cards from the deck.
@@ -136,6 +515,8 @@ impl Deck {
 mod test {
     use crate::card;
     use crate::poker;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
 
     fn default_commune() -> poker::Commune {
         poker::Commune {
@@ -196,6 +577,34 @@ mod test {
         assert_eq!(52, deck.cards.len());
     }
 
+    #[test]
+    fn seeded_deck() {
+        let first = poker::Deck::get_shuffled_decks(1, &mut StdRng::seed_from_u64(7));
+        let second = poker::Deck::get_shuffled_decks(1, &mut StdRng::seed_from_u64(7));
+        let other = poker::Deck::get_shuffled_decks(1, &mut StdRng::seed_from_u64(8));
+        assert_eq!(first.cards, second.cards);
+        assert_ne!(first.cards, other.cards);
+    }
+
+    #[test]
+    fn multiple_decks() {
+        let deck = poker::Deck::get_multiple_decks(2);
+        assert_eq!(104, deck.len());
+    }
+
+    #[test]
+    fn contains_handvalue_multiple_decks() {
+        let queen_of_spades = card::Card {
+            rank: card::Rank::Queen,
+            suit: card::Suit::Spades,
+        };
+        let commune = poker::Commune {
+            cards: vec![queen_of_spades; 3],
+        };
+        assert!(commune.contains_handvalue(poker::HandValue::ThreeOfAKind(card::Rank::Queen)));
+        assert!(!commune.contains_handvalue(poker::HandValue::FourOfAKind(card::Rank::Queen)));
+    }
+
     #[test]
     fn deal_cards_valid() {
         let mut deck = poker::Deck::get_full_deck();
@@ -212,6 +621,84 @@ mod test {
         assert!(should_be_error.is_err());
     }
 
+    #[test]
+    fn handvalue_validity() {
+        assert!(poker::HandValue::TwoPair(card::Rank::Queen, card::Rank::Nine).is_valid());
+        assert!(!poker::HandValue::TwoPair(card::Rank::Nine, card::Rank::Nine).is_valid());
+        assert!(!poker::HandValue::FullHouse(card::Rank::Queen, card::Rank::Queen).is_valid());
+        assert!(!poker::HandValue::Straight(card::Rank::Four).is_valid());
+        assert!(poker::HandValue::Straight(card::Rank::Six).is_valid());
+    }
+
+    #[test]
+    fn handvalue_ordering() {
+        assert!(
+            poker::HandValue::Straight(card::Rank::Ace)
+                < poker::HandValue::Flush(card::Rank::Seven, card::Suit::Clubs)
+        );
+        assert!(
+            poker::HandValue::Flush(card::Rank::Ace, card::Suit::Spades)
+                < poker::HandValue::FullHouse(card::Rank::Two, card::Rank::Three)
+        );
+        assert!(
+            poker::HandValue::Flush(card::Rank::Nine, card::Suit::Spades)
+                < poker::HandValue::Flush(card::Rank::Ten, card::Suit::Clubs)
+        );
+        assert!(
+            poker::HandValue::FourOfAKind(card::Rank::Ace)
+                < poker::HandValue::StraightFlush(card::Rank::Six, card::Suit::Clubs)
+        );
+        assert!(
+            poker::HandValue::StraightFlush(card::Rank::King, card::Suit::Spades)
+                < poker::HandValue::RoyalFlush(card::Suit::Clubs)
+        );
+    }
+
+    #[test]
+    fn all_handvalues() {
+        let values = poker::HandValue::all_values();
+        assert_eq!(
+            Some(&poker::HandValue::HighCard(card::Rank::Two)),
+            values.first()
+        );
+        assert_eq!(
+            Some(&poker::HandValue::RoyalFlush(card::Suit::Spades)),
+            values.last()
+        );
+        assert!(values.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(!values.contains(&poker::HandValue::TwoPair(
+            card::Rank::Two,
+            card::Rank::Three
+        )));
+        assert!(values.contains(&poker::HandValue::TwoPair(
+            card::Rank::Three,
+            card::Rank::Two
+        )));
+        assert_eq!(13 * 4 + 10 + 78 + 156 + 4 * 9 + 4 * 9 + 4, values.len());
+    }
+
+    #[test]
+    fn handvalue_normalized() {
+        assert_eq!(
+            poker::HandValue::TwoPair(card::Rank::Queen, card::Rank::Nine),
+            poker::HandValue::TwoPair(card::Rank::Nine, card::Rank::Queen).normalized()
+        );
+        assert_eq!(
+            poker::HandValue::RoyalFlush(card::Suit::Hearts),
+            poker::HandValue::StraightFlush(card::Rank::Ace, card::Suit::Hearts).normalized()
+        );
+    }
+
+    #[test]
+    fn return_cards() {
+        let mut deck = poker::Deck::get_full_deck();
+        let hand = deck.deal_cards(5).unwrap();
+        let returned = hand.cards.clone();
+        deck.return_cards(hand);
+        assert_eq!(52, deck.len());
+        assert_eq!(returned, deck.cards[..5].to_vec());
+    }
+
     #[test]
     fn contains_handvalue_pairs_triples() {
         let commune = default_commune();
@@ -229,4 +716,166 @@ mod test {
         assert!(!commune.contains_handvalue(poker::HandValue::Straight(card::Rank::Eight)));
         assert!(!commune.contains_handvalue(poker::HandValue::Straight(card::Rank::King)));
     }
+
+    #[test]
+    fn contains_handvalue_wheel() {
+        use card::Rank::*;
+        let mut commune = suited_commune(card::Suit::Diamonds, &[Ace, Two, Three, Four]);
+        assert!(!commune.contains_handvalue(poker::HandValue::Straight(Five)));
+        commune.cards.push(card::Card {
+            rank: Five,
+            suit: card::Suit::Clubs,
+        });
+        assert!(commune.contains_handvalue(poker::HandValue::Straight(Five)));
+        assert!(!commune.contains_handvalue(poker::HandValue::Straight(Six)));
+        assert!(!commune.contains_handvalue(poker::HandValue::Straight(Four)));
+        assert!(!commune
+            .contains_handvalue(poker::HandValue::StraightFlush(Five, card::Suit::Diamonds)));
+        commune.cards.push(card::Card {
+            rank: Five,
+            suit: card::Suit::Diamonds,
+        });
+        assert!(
+            commune.contains_handvalue(poker::HandValue::StraightFlush(Five, card::Suit::Diamonds))
+        );
+    }
+
+    #[test]
+    fn wheel_ordering() {
+        assert!(poker::HandValue::Straight(card::Rank::Five).is_valid());
+        assert!(!poker::HandValue::Straight(card::Rank::Four).is_valid());
+        assert!(
+            poker::HandValue::Straight(card::Rank::Five)
+                < poker::HandValue::Straight(card::Rank::Six)
+        );
+    }
+
+    fn suited_commune(suit: card::Suit, ranks: &[card::Rank]) -> poker::Commune {
+        poker::Commune {
+            cards: ranks
+                .iter()
+                .map(|rank| card::Card { suit, rank: *rank })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn contains_handvalue_flush() {
+        use card::Rank::*;
+        let mut commune = suited_commune(card::Suit::Hearts, &[Two, Five, Nine, Jack, King]);
+        commune.cards.extend(default_commune().cards);
+        assert!(commune.contains_handvalue(poker::HandValue::Flush(King, card::Suit::Hearts)));
+        assert!(commune.contains_handvalue(poker::HandValue::Flush(Jack, card::Suit::Hearts)));
+        assert!(!commune.contains_handvalue(poker::HandValue::Flush(Nine, card::Suit::Hearts)));
+        assert!(!commune.contains_handvalue(poker::HandValue::Flush(Ace, card::Suit::Hearts)));
+        assert!(!commune.contains_handvalue(poker::HandValue::Flush(King, card::Suit::Spades)));
+    }
+
+    #[test]
+    fn contains_handvalue_straight_flush() {
+        use card::Rank::*;
+        let commune = suited_commune(card::Suit::Clubs, &[Ten, Jack, Queen, King, Ace, Nine]);
+        assert!(commune.contains_handvalue(poker::HandValue::RoyalFlush(card::Suit::Clubs)));
+        assert!(
+            commune.contains_handvalue(poker::HandValue::StraightFlush(King, card::Suit::Clubs))
+        );
+        assert!(
+            !commune.contains_handvalue(poker::HandValue::StraightFlush(Queen, card::Suit::Clubs))
+        );
+        assert!(!commune.contains_handvalue(poker::HandValue::RoyalFlush(card::Suit::Hearts)));
+        assert!(!default_commune()
+            .contains_handvalue(poker::HandValue::StraightFlush(Queen, card::Suit::Spades)));
+    }
+
+    #[test]
+    fn evidence() {
+        use card::Rank::*;
+        let commune = default_commune();
+        let ranks = |value| -> Vec<card::Rank> {
+            commune
+                .evidence(value)
+                .iter()
+                .map(|card| card.rank)
+                .collect()
+        };
+        assert_eq!(vec![Queen, Queen], ranks(poker::HandValue::OnePair(Queen)));
+        assert_eq!(
+            vec![Queen, Queen, Queen, Nine, Nine],
+            ranks(poker::HandValue::FullHouse(Queen, Nine))
+        );
+        assert_eq!(
+            vec![Eight, Nine, Ten, Jack, Queen],
+            ranks(poker::HandValue::Straight(Queen))
+        );
+        // Only the part of a missing HandValue that exists is evidence.
+        assert_eq!(vec![Three], ranks(poker::HandValue::ThreeOfAKind(Three)));
+        assert_eq!(
+            vec![Nine, Ten, Jack, Queen],
+            ranks(poker::HandValue::Straight(King))
+        );
+        let flush = suited_commune(card::Suit::Hearts, &[Two, Five, Nine, Jack, King, Three]);
+        assert_eq!(
+            vec![King, Jack, Nine, Five, Three],
+            flush
+                .evidence(poker::HandValue::Flush(King, card::Suit::Hearts))
+                .iter()
+                .map(|card| card.rank)
+                .collect::<Vec<card::Rank>>()
+        );
+    }
+
+    #[test]
+    fn parse_hand_value() {
+        let full_house = poker::HandValue::FullHouse(card::Rank::Queen, card::Rank::Nine);
+        for text in &["fullhouse Q 9", "Full House queens over nines", "boat 12 9"] {
+            assert_eq!(Ok(full_house), text.parse());
+        }
+        let two_pair = poker::HandValue::TwoPair(card::Rank::Queen, card::Rank::Nine);
+        assert_eq!(Ok(two_pair), "two pair Q 9".parse());
+        assert_eq!(Ok(two_pair), "TWOPAIR queens, nines".parse());
+        let trips = poker::HandValue::ThreeOfAKind(card::Rank::Six);
+        assert_eq!(Ok(trips), "trips sixes".parse());
+        let nines = poker::HandValue::TwoPair(card::Rank::Nine, card::Rank::Five);
+        assert_eq!(Ok(nines), "two pair 9s 5s".parse());
+        let tens = poker::HandValue::OnePair(card::Rank::Ten);
+        assert_eq!(Ok(tens), "pair 10s".parse());
+        let deuces = poker::HandValue::ThreeOfAKind(card::Rank::Two);
+        assert_eq!(Ok(deuces), "trips 2s".parse());
+        let nine_flush = poker::HandValue::Flush(card::Rank::Nine, card::Suit::Spades);
+        assert_eq!(Ok(nine_flush), "flush 9s".parse());
+        let flush = poker::HandValue::StraightFlush(card::Rank::Nine, card::Suit::Hearts);
+        assert_eq!(Ok(flush), "straight flush 9 hearts".parse());
+        let royal = poker::HandValue::RoyalFlush(card::Suit::Spades);
+        assert_eq!(Ok(royal), "royal flush S".parse());
+        for value in poker::HandValue::all_values() {
+            assert_eq!(Ok(value), value.to_short_string().parse());
+        }
+    }
+
+    #[test]
+    fn display_hand_value() {
+        let full_house = poker::HandValue::FullHouse(card::Rank::Queen, card::Rank::Nine);
+        assert_eq!("Queens full of Nines", full_house.to_string());
+        assert_eq!("FH Q/9", full_house.to_short_string());
+        let straight = poker::HandValue::Straight(card::Rank::Jack);
+        assert_eq!("Straight to the Jack", straight.to_string());
+        let pair = poker::HandValue::OnePair(card::Rank::Ace);
+        assert_eq!("Pair of Aces", pair.to_string());
+        let flush = poker::HandValue::Flush(card::Rank::King, card::Suit::Hearts);
+        assert_eq!("Flush in Hearts to the King", flush.to_string());
+        assert_eq!("FL KH", flush.to_short_string());
+        let trips = poker::HandValue::ThreeOfAKind(card::Rank::Six);
+        assert_eq!("Three Sixes", trips.to_string());
+    }
+
+    #[test]
+    fn parse_errors() {
+        let error = "pair".parse::<poker::HandValue>().unwrap_err();
+        assert_eq!("expected a bet like \"pair Q\"", error.reason);
+        let error = "pair of queers".parse::<poker::HandValue>().unwrap_err();
+        assert_eq!("\"queers\" is not a rank or a suit", error.reason);
+        assert!("fold".parse::<poker::HandValue>().is_err());
+        assert!("".parse::<poker::HandValue>().is_err());
+        assert!("flush Q".parse::<poker::HandValue>().is_err());
+    }
 }
@@ -0,0 +1,95 @@
+use crate::game::{Event, GameError, GameState};
+
+/// Every state a game passed through, rebuilt from its history so that it
+/// can be stepped through, and forked, move by move.
+#[derive(Clone, Debug)]
+pub struct Replay {
+    /// The game as dealt, then after each move of the history.
+    states: Vec<GameState>,
+    /// The events of the deal, then of each move.
+    events: Vec<Vec<Event>>,
+}
+
+impl Replay {
+    pub fn new(game: &GameState) -> Result<Self, GameError> {
+        let mut state = game.initial_state()?;
+        let mut events = vec![state.round_started_events()];
+        let mut states = vec![state.clone()];
+        for game_move in &game.history {
+            events.push(state.process_move(*game_move)?);
+            states.push(state.clone());
+        }
+        Ok(Self { states, events })
+    }
+
+    /// Return the number of moves in the replay.
+    pub fn len(&self) -> usize {
+        self.states.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Return the game after `step` moves. Step 0 is the deal.
+    pub fn state(&self, step: usize) -> &GameState {
+        &self.states[step]
+    }
+
+    /// Return what happened in move `step`, or in the deal for step 0.
+    pub fn events(&self, step: usize) -> &[Event] {
+        &self.events[step]
+    }
+
+    /// Return a live copy of the game after `step` moves to play on from.
+    pub fn fork(&self, step: usize) -> GameState {
+        self.states[step].clone()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::card;
+    use crate::game;
+    use crate::poker;
+    use crate::replay;
+    use crate::rules;
+
+    fn played_game() -> game::GameState {
+        let mut state =
+            game::GameState::init_game_with_seed(3, rules::GameRules::default(), 11).unwrap();
+        for rank in &[card::Rank::Two, card::Rank::Three] {
+            let bet = poker::HandValue::OnePair(*rank);
+            state.process_move(game::GameMove::Bet(bet)).unwrap();
+            state.process_move(game::GameMove::Call()).unwrap();
+        }
+        state
+    }
+
+    #[test]
+    fn replays_the_game() {
+        let state = played_game();
+        let replay = replay::Replay::new(&state).unwrap();
+        assert_eq!(4, replay.len());
+        assert_eq!(&state, replay.state(4));
+        assert_eq!(&state.initial_state().unwrap(), replay.state(0));
+        assert!(matches!(
+            replay.events(0)[0],
+            game::Event::RoundStarted { starter: 0 }
+        ));
+        assert!(matches!(replay.events(2)[1], game::Event::CallResolved(_)));
+    }
+
+    #[test]
+    fn fork() {
+        let state = played_game();
+        let replay = replay::Replay::new(&state).unwrap();
+        let mut fork = replay.fork(2);
+        assert_eq!(2, fork.history.len());
+        let bet = poker::HandValue::HighCard(card::Rank::Ace);
+        fork.process_move(game::GameMove::Bet(bet)).unwrap();
+        assert_eq!(3, fork.history.len());
+        assert_eq!(Some(bet), fork.current_bet);
+        assert_eq!(replay.state(2).players, fork.players);
+    }
+}
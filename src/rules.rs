@@ -0,0 +1,143 @@
+use crate::game::{GameError, GameResult};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Who leads the betting after a call has been resolved.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub enum RoundStarter {
+    /// The penalized player, or the next player in if they were eliminated.
+    Loser,
+    /// The player on the right side of the call.
+    Winner,
+    /// The seat after whoever started the previous round.
+    NextSeat,
+}
+
+/// How far back players may take moves back.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub enum UndoRule {
+    /// Any number of moves, back to the deal.
+    #[default]
+    Anytime,
+    /// Only the last move, before the next player has acted on it.
+    UntilNextMove,
+    /// No moves at all, for competitive games.
+    Never,
+}
+
+/// The house rules a game is played under.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct GameRules {
+    /// A player is eliminated once they have taken this many penalties.
+    pub max_penalties: u8,
+    /// Cards dealt to a player with no penalties.
+    pub starting_hand_size: u8,
+    /// Extra cards dealt for every penalty a player has taken.
+    pub cards_per_penalty: u8,
+    /// Number of standard 52 card decks shuffled together.
+    pub num_decks: u8,
+    /// A player who calls correctly has one penalty taken away.
+    pub shrink_on_successful_call: bool,
+    pub next_round_starter: RoundStarter,
+    /// Shuffle the reclaimed cards before every deal. When false, returned
+    /// hands go to the bottom of the deck and come back around in order.
+    pub reshuffle: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub undo: UndoRule,
+}
+
+impl Default for GameRules {
+    fn default() -> Self {
+        Self {
+            max_penalties: 3,
+            starting_hand_size: 1,
+            cards_per_penalty: 1,
+            num_decks: 1,
+            shrink_on_successful_call: false,
+            next_round_starter: RoundStarter::Loser,
+            reshuffle: true,
+            undo: UndoRule::Anytime,
+        }
+    }
+}
+
+impl GameRules {
+    /// Return an error describing the first setting that cannot be played.
+    pub fn validate(&self) -> GameResult {
+        if self.max_penalties == 0 {
+            Err(GameError::InvalidRules(
+                "Players must be allowed at least one penalty.",
+            ))
+        } else if self.starting_hand_size == 0 {
+            Err(GameError::InvalidRules(
+                "Players must start with at least one card.",
+            ))
+        } else if self.num_decks == 0 {
+            Err(GameError::InvalidRules("At least one deck is needed."))
+        } else if self.shrink_on_successful_call && self.cards_per_penalty == 0 {
+            Err(GameError::InvalidRules(
+                "Hands cannot shrink when penalties do not add cards.",
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Return the number of cards dealt to a player with the given penalties.
+    pub fn hand_size(&self, penalties: u8) -> usize {
+        self.starting_hand_size as usize + penalties as usize * self.cards_per_penalty as usize
+    }
+
+    /// Return the largest hand a player still in the game can hold.
+    pub fn max_hand_size(&self) -> usize {
+        self.hand_size(self.max_penalties.saturating_sub(1))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::rules;
+
+    #[test]
+    fn default_rules() {
+        let rules = rules::GameRules::default();
+        assert!(rules.validate().is_ok());
+        assert_eq!(1, rules.hand_size(0));
+        assert_eq!(3, rules.max_hand_size());
+    }
+
+    #[test]
+    fn hand_size() {
+        let rules = rules::GameRules {
+            max_penalties: 4,
+            starting_hand_size: 2,
+            cards_per_penalty: 2,
+            ..rules::GameRules::default()
+        };
+        assert_eq!(4, rules.hand_size(1));
+        assert_eq!(8, rules.max_hand_size());
+    }
+
+    #[test]
+    fn invalid_rules() {
+        let no_decks = rules::GameRules {
+            num_decks: 0,
+            ..rules::GameRules::default()
+        };
+        assert!(no_decks.validate().is_err());
+        let empty_hands = rules::GameRules {
+            starting_hand_size: 0,
+            ..rules::GameRules::default()
+        };
+        assert!(empty_hands.validate().is_err());
+        let nothing_to_shrink = rules::GameRules {
+            cards_per_penalty: 0,
+            shrink_on_successful_call: true,
+            ..rules::GameRules::default()
+        };
+        assert!(nothing_to_shrink.validate().is_err());
+    }
+}
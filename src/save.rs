@@ -0,0 +1,258 @@
+use crate::card::Card;
+use crate::game::{GameError, GamePhase, GameState};
+use crate::poker::Deck;
+use serde::{Deserialize, Serialize};
+
+/// The version of the saved game format. Bump it whenever a change to the
+/// game types would stop older files from loading the same way.
+pub const FORMAT_VERSION: u64 = 1;
+
+#[derive(Serialize)]
+struct SaveFile<'a> {
+    version: u64,
+    game: &'a GameState,
+}
+
+/// The version alone, read before the rest of a file.
+#[derive(Deserialize)]
+struct Version {
+    version: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct LoadFile {
+    game: GameState,
+}
+
+/// Return the game as versioned JSON.
+pub fn to_json(state: &GameState) -> String {
+    let file = SaveFile {
+        version: FORMAT_VERSION,
+        game: state,
+    };
+    serde_json::to_string_pretty(&file).expect("games always serialize")
+}
+
+/// Read a game written by to_json. The version is checked before anything
+/// else so that files from other releases are rejected clearly.
+pub fn from_json(text: &str) -> Result<GameState, GameError> {
+    let invalid = |e: serde_json::Error| GameError::InvalidSave(e.to_string());
+    let version = serde_json::from_str::<Version>(text)
+        .map_err(invalid)?
+        .version
+        .ok_or_else(|| GameError::InvalidSave("missing version".to_owned()))?;
+    if version != FORMAT_VERSION {
+        return Err(GameError::UnsupportedSaveVersion(version));
+    }
+    let state = serde_json::from_str::<LoadFile>(text)
+        .map_err(invalid)?
+        .game;
+    check_game(&state).map_err(|reason| GameError::InvalidSave(reason.to_owned()))?;
+    Ok(state)
+}
+
+/// Check that a loaded game could have been reached by playing, so that an
+/// edited or damaged file cannot put the engine in an impossible state.
+fn check_game(state: &GameState) -> Result<(), &'static str> {
+    let rules = &state.rules;
+    rules.validate().map_err(|_| "the rules are invalid")?;
+    let num_seats = state.players.len();
+    if num_seats < 2 {
+        return Err("there must be at least two players");
+    }
+    if state
+        .players
+        .iter()
+        .enumerate()
+        .any(|(seat, player)| player.name as usize != seat)
+    {
+        return Err("players must be named after their seats");
+    }
+    if state.current_turn >= num_seats || state.round_starter >= num_seats {
+        return Err("a seat is out of range");
+    }
+
+    let mut cards: Vec<Card> = state.deck.cards().to_vec();
+    for player in &state.players {
+        let expected_size = if player.is_out(rules) {
+            0
+        } else {
+            rules.hand_size(player.penalties)
+        };
+        if player.hand.cards.len() != expected_size {
+            return Err("a hand has the wrong number of cards");
+        }
+        cards.extend(&player.hand.cards);
+    }
+    let mut all_cards: Vec<Card> = Deck::get_ordered_decks(rules.num_decks).cards().to_vec();
+    cards.sort_by_key(|card| (card.rank, card.suit));
+    all_cards.sort_by_key(|card| (card.rank, card.suit));
+    if cards != all_cards {
+        return Err("the cards do not make up whole decks");
+    }
+
+    if state.current_bet != state.round_bets.last().map(|(_, bet)| *bet) {
+        return Err("the current bet is not the last bet of the round");
+    }
+    for (index, (seat, bet)) in state.round_bets.iter().enumerate() {
+        let raises = index == 0 || state.round_bets[index - 1].1 < *bet;
+        if *seat >= num_seats || !bet.is_valid() || *bet != bet.normalized() || !raises {
+            return Err("the bets of the round are not legal");
+        }
+    }
+
+    // Replaying leaves nothing to redo, so the undone moves are checked
+    // separately below.
+    let replayed = state
+        .state_after(state.history.len())
+        .map(|replayed| GameState {
+            undone: state.undone.clone(),
+            ..replayed
+        });
+    match replayed {
+        Ok(replayed) if replayed == *state => (),
+        _ => return Err("the history does not lead to this game"),
+    }
+    let mut redone = state.clone();
+    while !redone.undone.is_empty() {
+        if redone.redo().is_err() {
+            return Err("the undone moves cannot be played again");
+        }
+    }
+
+    let active: Vec<usize> = state
+        .players
+        .iter()
+        .filter(|player| !player.is_out(rules))
+        .map(|player| player.name as usize)
+        .collect();
+    match state.phase {
+        GamePhase::InProgress if active.len() < 2 => Err("the game should be over"),
+        GamePhase::InProgress if !active.contains(&state.current_turn) => {
+            Err("an eliminated player has the turn")
+        }
+        GamePhase::Finished { winner } if active != [winner as usize] => {
+            Err("the winner is not the last player left")
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::card;
+    use crate::game;
+    use crate::poker;
+    use crate::rules;
+    use crate::save;
+
+    #[test]
+    fn cards_are_plain_text() {
+        let hand = poker::Hand {
+            cards: vec![
+                card::Card {
+                    rank: card::Rank::Queen,
+                    suit: card::Suit::Spades,
+                },
+                card::Card {
+                    rank: card::Rank::Ten,
+                    suit: card::Suit::Hearts,
+                },
+            ],
+        };
+        assert_eq!(r#"["QS","10H"]"#, serde_json::to_string(&hand).unwrap());
+        let read: poker::Hand = serde_json::from_str(r#"["QS","10H"]"#).unwrap();
+        assert_eq!(hand, read);
+        assert!(serde_json::from_str::<card::Card>(r#""11H""#).is_err());
+        let value = poker::HandValue::TwoPair(card::Rank::Queen, card::Rank::Nine);
+        assert_eq!(
+            r#"{"TwoPair":["Q","9"]}"#,
+            serde_json::to_string(&value).unwrap()
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut state =
+            game::GameState::init_game_with_seed(3, rules::GameRules::default(), 7).unwrap();
+        let bet = poker::HandValue::OnePair(card::Rank::Two);
+        state.process_move(game::GameMove::Bet(bet)).unwrap();
+        state.process_move(game::GameMove::Call()).unwrap();
+
+        let mut loaded = save::from_json(&save::to_json(&state)).unwrap();
+        assert_eq!(save::to_json(&state), save::to_json(&loaded));
+        assert_eq!(state.deck.cards(), loaded.deck.cards());
+
+        // Both copies play on identically, down to the next shuffle.
+        for game_state in [&mut state, &mut loaded] {
+            game_state.process_move(game::GameMove::Bet(bet)).unwrap();
+            game_state.process_move(game::GameMove::Call()).unwrap();
+        }
+        assert_eq!(save::to_json(&state), save::to_json(&loaded));
+
+        state.undo().unwrap();
+        let loaded = save::from_json(&save::to_json(&state)).unwrap();
+        assert_eq!(vec![game::GameMove::Call()], loaded.undone);
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        let state =
+            game::GameState::init_game_with_seed(2, rules::GameRules::default(), 7).unwrap();
+        for &version in &[0, 99] {
+            let text = save::to_json(&state).replacen(
+                "\"version\": 1",
+                &format!("\"version\": {}", version),
+                1,
+            );
+            assert!(matches!(
+                save::from_json(&text),
+                Err(game::GameError::UnsupportedSaveVersion(v)) if v == version
+            ));
+        }
+        assert!(matches!(
+            save::from_json("{\"game\": {}}"),
+            Err(game::GameError::InvalidSave(_))
+        ));
+        assert!(matches!(
+            save::from_json("not json"),
+            Err(game::GameError::InvalidSave(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_impossible_games() {
+        let state =
+            game::GameState::init_game_with_seed(2, rules::GameRules::default(), 7).unwrap();
+        let mut extra_card = state.clone();
+        let card = extra_card.players[1].hand.cards[0];
+        extra_card.players[0].hand.cards.push(card);
+        let mut bad_turn = state.clone();
+        bad_turn.current_turn = 5;
+        let mut stray_bet = state.clone();
+        stray_bet.current_bet = Some(poker::HandValue::HighCard(card::Rank::Two));
+        let mut wrong_history = state.clone();
+        wrong_history.history.push(game::GameMove::Call());
+        let mut wrong_redo = state.clone();
+        wrong_redo.undone.push(game::GameMove::Call());
+        let mut missing_history = state.clone();
+        let bet = poker::HandValue::HighCard(card::Rank::Two);
+        missing_history
+            .process_move(game::GameMove::Bet(bet))
+            .unwrap();
+        missing_history.history.clear();
+        for bad_state in &[
+            extra_card,
+            bad_turn,
+            stray_bet,
+            wrong_history,
+            wrong_redo,
+            missing_history,
+        ] {
+            assert!(matches!(
+                save::from_json(&save::to_json(bad_state)),
+                Err(game::GameError::InvalidSave(_))
+            ));
+        }
+    }
+}
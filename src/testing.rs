@@ -0,0 +1,36 @@
+//! Helpers shared by the tests of the bots and the engine.
+
+use crate::agent::{self, Agent, PlayerView};
+use crate::card::{Card, Rank, Suit};
+use crate::game::GameState;
+use crate::poker::{Hand, HandValue};
+use crate::rules::GameRules;
+
+pub fn card(rank: Rank, suit: Suit) -> Card {
+    Card { rank, suit }
+}
+
+/// Return the view of seat 0 in a two player game where both players hold
+/// two cards, and seat 1 made `current_bet` if there is one.
+pub fn view(hand: Vec<Card>, current_bet: Option<HandValue>) -> PlayerView {
+    PlayerView {
+        seat: 0,
+        hand: Hand { cards: hand },
+        card_counts: vec![2, 2],
+        penalties: vec![0, 0],
+        current_bet,
+        round_bets: current_bet.map(|bet| (1, bet)).into_iter().collect(),
+        rules: GameRules::default(),
+    }
+}
+
+/// Let the agents play until the game is over, and fail the test if they
+/// make an illegal move or take too long.
+pub fn play_game(state: &mut GameState, agents: &mut [Box<dyn Agent>]) {
+    let mut turns = 0;
+    while !state.is_over() && turns < 1000 {
+        agent::play_turn(state, agents).unwrap();
+        turns += 1;
+    }
+    assert!(state.is_over());
+}
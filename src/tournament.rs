@@ -0,0 +1,455 @@
+use crate::agent::{self, Agent};
+use crate::analytics;
+use crate::bot::{ProbabilityBot, StrategyBot};
+use crate::cfr::Strategy;
+use crate::game::{Event, GameError, GamePhase, GameState};
+use crate::mcts::MctsBot;
+use crate::rules::GameRules;
+use std::fmt;
+use std::fs;
+use std::str::FromStr;
+
+/// A kind of computer player that can be entered in a tournament.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AgentSpec {
+    Probability {
+        aggression: f64,
+        bluff_frequency: f64,
+    },
+    Mcts {
+        iterations: usize,
+    },
+    /// Follows a strategy table exported by the solver, read from `path`.
+    Strategy {
+        path: String,
+        strategy: Strategy,
+    },
+}
+
+impl AgentSpec {
+    pub fn build(&self, seed: u64) -> Box<dyn Agent> {
+        match self {
+            AgentSpec::Probability {
+                aggression,
+                bluff_frequency,
+            } => Box::new(ProbabilityBot::new(*aggression, *bluff_frequency, seed)),
+            AgentSpec::Mcts { iterations } => Box::new(MctsBot::new(*iterations, seed)),
+            AgentSpec::Strategy { strategy, .. } => {
+                Box::new(StrategyBot::new(strategy.clone(), seed))
+            }
+        }
+    }
+}
+
+impl fmt::Display for AgentSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AgentSpec::Probability {
+                aggression,
+                bluff_frequency,
+            } => write!(f, "prob:{}:{}", aggression, bluff_frequency),
+            AgentSpec::Mcts { iterations } => write!(f, "mcts:{}", iterations),
+            AgentSpec::Strategy { path, .. } => write!(f, "strategy:{}", path),
+        }
+    }
+}
+
+impl FromStr for AgentSpec {
+    type Err = String;
+
+    /// Parse specs like "prob", "prob:<aggression>:<bluff frequency>",
+    /// "mcts:<iterations>" or "strategy:<file>". Strategy files are read
+    /// straight away.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(path) = s.strip_prefix("strategy:") {
+            let text =
+                fs::read_to_string(path).map_err(|e| format!("Could not read {}: {}", path, e))?;
+            let strategy = Strategy::from_text(&text).map_err(|e| format!("In {}: {}", path, e))?;
+            return Ok(AgentSpec::Strategy {
+                path: path.to_owned(),
+                strategy,
+            });
+        }
+        let parts: Vec<&str> = s.split(':').collect();
+        let number = |index: usize, default: f64| -> Result<f64, String> {
+            match parts.get(index) {
+                None => Ok(default),
+                Some(part) => part
+                    .parse()
+                    .map_err(|_| format!("\"{}\" is not a number in \"{}\".", part, s)),
+            }
+        };
+        match parts[0] {
+            "prob" if parts.len() <= 3 => Ok(AgentSpec::Probability {
+                aggression: number(1, 0.3)?,
+                bluff_frequency: number(2, 0.1)?,
+            }),
+            "mcts" if parts.len() <= 2 => Ok(AgentSpec::Mcts {
+                iterations: match parts.get(1) {
+                    None => 200,
+                    Some(part) => part
+                        .parse()
+                        .map_err(|_| format!("\"{}\" is not a count in \"{}\".", part, s))?,
+                },
+            }),
+            _ => Err(format!("Unknown agent \"{}\".", s)),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TournamentConfig {
+    /// One entry per seat. Entries move one seat along every game.
+    pub lineup: Vec<AgentSpec>,
+    pub num_games: usize,
+    pub seed: u64,
+    pub rules: GameRules,
+    /// Games still going after this many turns are abandoned.
+    pub max_turns: usize,
+}
+
+/// A value measured once per game, summarized as a mean.
+#[derive(Clone, Debug, Default)]
+pub struct Sample {
+    count: usize,
+    sum: f64,
+    sum_of_squares: f64,
+}
+
+impl Sample {
+    pub fn add(&mut self, value: f64) {
+        self.count += 1;
+        self.sum += value;
+        self.sum_of_squares += value * value;
+    }
+
+    pub fn mean(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.sum / self.count as f64
+        }
+    }
+
+    /// Return the half width of the 95% confidence interval of the mean.
+    pub fn margin(&self) -> f64 {
+        if self.count < 2 {
+            return 0.0;
+        }
+        let n = self.count as f64;
+        let variance = (self.sum_of_squares - self.sum * self.sum / n) / (n - 1.0);
+        analytics::Z_95 * (variance.max(0.0) / n).sqrt()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct AgentStats {
+    pub spec: AgentSpec,
+    pub games: usize,
+    pub wins: usize,
+    pub penalties: Sample,
+    pub calls: usize,
+    pub correct_calls: usize,
+}
+
+impl AgentStats {
+    pub fn win_rate(&self) -> analytics::Estimate {
+        analytics::wilson_interval(self.wins, self.games)
+    }
+
+    pub fn call_accuracy(&self) -> analytics::Estimate {
+        analytics::wilson_interval(self.correct_calls, self.calls)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TournamentReport {
+    pub agents: Vec<AgentStats>,
+    pub games: usize,
+    pub abandoned: usize,
+    /// The number of turns taken by each finished game.
+    pub game_length: Sample,
+}
+
+/// Play `config.num_games` games between the agents in the lineup.
+pub fn run_tournament(config: &TournamentConfig) -> Result<TournamentReport, GameError> {
+    let num_seats = config.lineup.len();
+    let mut report = TournamentReport {
+        agents: config
+            .lineup
+            .iter()
+            .map(|spec| AgentStats {
+                spec: spec.clone(),
+                games: 0,
+                wins: 0,
+                penalties: Sample::default(),
+                calls: 0,
+                correct_calls: 0,
+            })
+            .collect(),
+        games: 0,
+        abandoned: 0,
+        game_length: Sample::default(),
+    };
+
+    for game in 0..config.num_games {
+        let game_seed = config.seed.wrapping_add(game as u64);
+        let mut state =
+            GameState::init_game_with_seed(num_seats as u8, config.rules.clone(), game_seed)?;
+        // Rotate the lineup so every entry plays from every seat.
+        let entry_in_seat: Vec<usize> = (0..num_seats)
+            .map(|seat| (seat + game) % num_seats)
+            .collect();
+        let mut agents: Vec<Box<dyn Agent>> = entry_in_seat
+            .iter()
+            .enumerate()
+            .map(|(seat, &entry)| {
+                let agent_seed = game_seed.wrapping_mul(31).wrapping_add(seat as u64);
+                config.lineup[entry].build(agent_seed)
+            })
+            .collect();
+
+        let mut turns = 0;
+        while !state.is_over() && turns < config.max_turns {
+            for event in agent::play_turn(&mut state, &mut agents)? {
+                if let Event::CallResolved(outcome) = event {
+                    let stats = &mut report.agents[entry_in_seat[outcome.caller]];
+                    stats.calls += 1;
+                    if !outcome.bet_exists {
+                        stats.correct_calls += 1;
+                    }
+                }
+            }
+            turns += 1;
+        }
+
+        report.games += 1;
+        let winner = match state.phase {
+            GamePhase::Finished { winner } => winner as usize,
+            GamePhase::InProgress => {
+                report.abandoned += 1;
+                continue;
+            }
+        };
+        report.game_length.add(turns as f64);
+        for (seat, &entry) in entry_in_seat.iter().enumerate() {
+            let stats = &mut report.agents[entry];
+            stats.games += 1;
+            stats.penalties.add(state.players[seat].penalties as f64);
+            if seat == winner {
+                stats.wins += 1;
+            }
+        }
+    }
+    Ok(report)
+}
+
+impl TournamentReport {
+    /// Render the report as a plain text table.
+    pub fn to_table(&self) -> String {
+        let mut output = format!(
+            "{:<5} {:<20} {:>6} {:>22} {:>16} {:>22}\n",
+            "Entry", "Agent", "Wins", "Win rate (95% CI)", "Penalties", "Call accuracy"
+        );
+        for (entry, stats) in self.agents.iter().enumerate() {
+            let win_rate = stats.win_rate();
+            let accuracy = stats.call_accuracy();
+            output.push_str(&format!(
+                "{:<5} {:<20} {:>6} {:>22} {:>16} {:>22}\n",
+                entry,
+                stats.spec.to_string(),
+                stats.wins,
+                format!(
+                    "{:.3} [{:.3}, {:.3}]",
+                    win_rate.probability, win_rate.lower, win_rate.upper
+                ),
+                format!(
+                    "{:.2} ± {:.2}",
+                    stats.penalties.mean(),
+                    stats.penalties.margin()
+                ),
+                format!(
+                    "{:.3} [{:.3}, {:.3}]",
+                    accuracy.probability, accuracy.lower, accuracy.upper
+                ),
+            ));
+        }
+        output.push_str(&format!(
+            "Games: {} ({} abandoned), average length {:.1} ± {:.1} turns\n",
+            self.games,
+            self.abandoned,
+            self.game_length.mean(),
+            self.game_length.margin()
+        ));
+        output
+    }
+
+    /// Render the report as a JSON document.
+    pub fn to_json(&self) -> String {
+        let agents: Vec<String> = self
+            .agents
+            .iter()
+            .map(|stats| {
+                format!(
+                    concat!(
+                        "{{\"agent\": {}, \"games\": {}, \"wins\": {}, ",
+                        "\"win_rate\": {}, \"average_penalties\": {}, ",
+                        "\"calls\": {}, \"call_accuracy\": {}}}"
+                    ),
+                    json_string(&stats.spec.to_string()),
+                    stats.games,
+                    stats.wins,
+                    estimate_json(&stats.win_rate()),
+                    sample_json(&stats.penalties),
+                    stats.calls,
+                    estimate_json(&stats.call_accuracy()),
+                )
+            })
+            .collect();
+        format!(
+            concat!(
+                "{{\"games\": {}, \"abandoned\": {}, ",
+                "\"average_game_length\": {}, \"agents\": [{}]}}"
+            ),
+            self.games,
+            self.abandoned,
+            sample_json(&self.game_length),
+            agents.join(", ")
+        )
+    }
+}
+
+fn estimate_json(estimate: &analytics::Estimate) -> String {
+    format!(
+        "{{\"mean\": {}, \"lower\": {}, \"upper\": {}}}",
+        json_number(estimate.probability),
+        json_number(estimate.lower),
+        json_number(estimate.upper)
+    )
+}
+
+fn sample_json(sample: &Sample) -> String {
+    format!(
+        "{{\"mean\": {}, \"margin\": {}}}",
+        json_number(sample.mean()),
+        json_number(sample.margin())
+    )
+}
+
+/// Write a number as JSON, which has no NaN or infinity.
+fn json_number(value: f64) -> String {
+    if value.is_finite() {
+        value.to_string()
+    } else {
+        "null".to_owned()
+    }
+}
+
+/// Quote `text` as a JSON string. Agent names include file paths, which may
+/// hold quotes, backslashes or control characters.
+fn json_string(text: &str) -> String {
+    let mut output = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            c if (c as u32) < 0x20 => output.push_str(&format!("\\u{:04x}", c as u32)),
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+    output
+}
+
+#[cfg(test)]
+mod test {
+    use crate::rules;
+    use crate::tournament;
+    use std::env;
+    use std::fs;
+
+    fn config(num_games: usize) -> tournament::TournamentConfig {
+        tournament::TournamentConfig {
+            lineup: vec!["prob:0.2:0.0".parse().unwrap(), "prob".parse().unwrap()],
+            num_games,
+            seed: 4,
+            rules: rules::GameRules::default(),
+            max_turns: 500,
+        }
+    }
+
+    #[test]
+    fn parse_agent_spec() {
+        assert_eq!(
+            Ok(tournament::AgentSpec::Probability {
+                aggression: 0.5,
+                bluff_frequency: 0.25
+            }),
+            "prob:0.5:0.25".parse()
+        );
+        assert_eq!(
+            Ok(tournament::AgentSpec::Mcts { iterations: 50 }),
+            "mcts:50".parse()
+        );
+        assert!("prob:fast".parse::<tournament::AgentSpec>().is_err());
+        assert!("human".parse::<tournament::AgentSpec>().is_err());
+        assert!("strategy:no/such/file"
+            .parse::<tournament::AgentSpec>()
+            .is_err());
+    }
+
+    #[test]
+    fn strategy_agent_spec() {
+        let path = env::temp_dir().join("commune-strategy-agent-spec.txt");
+        fs::write(&path, "JH|\t1P J=1\n").unwrap();
+        let text = format!("strategy:{}", path.display());
+        let spec: tournament::AgentSpec = text.parse().unwrap();
+        assert_eq!(text, spec.to_string());
+        match &spec {
+            tournament::AgentSpec::Strategy { strategy, .. } => {
+                assert_eq!(Some(&vec![("1P J".to_owned(), 1.0)]), strategy.get("JH|"))
+            }
+            _ => panic!("expected a strategy agent, got {}", spec),
+        }
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn sample() {
+        let mut sample = tournament::Sample::default();
+        for value in &[1.0, 2.0, 3.0, 4.0] {
+            sample.add(*value);
+        }
+        assert_eq!(2.5, sample.mean());
+        assert!((sample.margin() - 1.96 * (5.0f64 / 12.0).sqrt()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn tournament_is_reproducible() {
+        let first = tournament::run_tournament(&config(4)).unwrap();
+        let second = tournament::run_tournament(&config(4)).unwrap();
+        assert_eq!(first.to_table(), second.to_table());
+        assert_eq!(4, first.games);
+        let wins: usize = first.agents.iter().map(|stats| stats.wins).sum();
+        assert_eq!(first.games - first.abandoned, wins);
+        assert!(first.to_table().contains("prob:0.2:0"));
+    }
+
+    #[test]
+    fn report_json() {
+        let report = tournament::run_tournament(&config(4)).unwrap();
+        let json = report.to_json();
+        assert!(json.starts_with("{\"games\": 4, \"abandoned\": "));
+        assert!(json.contains("{\"agent\": \"prob:0.2:0\", \"games\": 4, "));
+        assert!(json.ends_with("}]}"));
+    }
+
+    #[test]
+    fn json_string() {
+        assert_eq!(
+            "\"C:\\\\bots\\\\\\\"a\\\"\\n\\u0009\"",
+            tournament::json_string("C:\\bots\\\"a\"\n\t")
+        );
+    }
+}